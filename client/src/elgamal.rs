@@ -0,0 +1,99 @@
+//! ElGamal encryption over the keys of TREX keychain.
+use crate::Error;
+use elgamal_trex::elgamal::{PrivateKey, PublicKey};
+use rand::{CryptoRng, RngCore};
+use rug::{integer::Order, Integer};
+
+/// Maximum length of a message in bytes which can be encrypted with a key of the bit length.
+/// One byte is reserved as a marker to keep the trailing zeros of a message.
+pub fn max_message_len(bit_length: u32) -> usize {
+	((bit_length.saturating_sub(1) / 8) as usize).saturating_sub(1)
+}
+
+/// Encode a message as an integer, the marker byte keeps the trailing zeros of the message.
+pub fn encode_message(message: &[u8]) -> Integer {
+	let mut digits = message.to_vec();
+	digits.push(1u8);
+	Integer::from_digits(&digits, Order::Lsf)
+}
+
+/// Decode a message from an integer encoded by `encode_message`.
+pub fn decode_message(int: &Integer) -> Option<Vec<u8>> {
+	let mut digits: Vec<u8> = int.to_digits(Order::Lsf);
+	match digits.pop() {
+		Some(1u8) => Some(digits),
+		_ => None,
+	}
+}
+
+/// Encrypt an integer smaller than the modulus with a public key, return (c1, c2).
+pub fn encrypt<R: RngCore + CryptoRng>(
+	key: &PublicKey,
+	message: &Integer,
+	rng: &mut R,
+) -> Result<(Integer, Integer), Error> {
+	if message >= &key.p {
+		return Err(Error::MessageTooLong {
+			len: message.significant_digits::<u8>(),
+			max: max_message_len(key.bit_length),
+		})
+	}
+	// draw the ephemeral key from [1, p - 2].
+	let mut bytes = [0u8; 32];
+	rng.fill_bytes(&mut bytes);
+	let range = Integer::from(&key.p - 2);
+	let k = Integer::from_digits(&bytes, Order::Lsf) % range + 1;
+	let c1 = Integer::from(key.g.pow_mod_ref(&k, &key.p).unwrap());
+	let s = Integer::from(key.h.pow_mod_ref(&k, &key.p).unwrap());
+	let c2 = Integer::from(s * message) % &key.p;
+	Ok((c1, c2))
+}
+
+/// Decrypt a pair of ciphertext integers with a private key.
+pub fn decrypt(key: &PrivateKey, c1: &Integer, c2: &Integer) -> Result<Integer, Error> {
+	if c1 >= &key.p || c2 >= &key.p {
+		return Err(Error::InvalidCiphertext)
+	}
+	let s = Integer::from(c1.pow_mod_ref(&key.x, &key.p).unwrap());
+	let s_inv = s.invert(&key.p).map_err(|_| Error::InvalidCiphertext)?;
+	Ok(Integer::from(s_inv * c2) % &key.p)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A key pair over a 38 bit prime for testing.
+	fn test_key_pair() -> (PublicKey, PrivateKey) {
+		let p = Integer::from(209805312383u64);
+		let g = Integer::from(38619647689u64);
+		let x = Integer::from(1234567u64);
+		let h = Integer::from(g.pow_mod_ref(&x, &p).unwrap());
+		(
+			PublicKey { p: p.clone(), g: g.clone(), h, bit_length: 38 },
+			PrivateKey { p, g, x, bit_length: 38 },
+		)
+	}
+
+	#[test]
+	fn encrypt_and_decrypt() {
+		let (pubkey, privkey) = test_key_pair();
+		let mut rng = rand::thread_rng();
+		let message = b"\x00a\x00";
+		assert_eq!(message.len(), max_message_len(pubkey.bit_length));
+		let (c1, c2) = encrypt(&pubkey, &encode_message(message), &mut rng).unwrap();
+		let plain = decrypt(&privkey, &c1, &c2).unwrap();
+		assert_eq!(decode_message(&plain).unwrap(), message.to_vec());
+	}
+
+	#[test]
+	fn reject_long_message() {
+		let (pubkey, _) = test_key_pair();
+		let mut rng = rand::thread_rng();
+		let message = encode_message(&[0xffu8; 5]);
+		assert!(matches!(
+			encrypt(&pubkey, &message, &mut rng),
+			Err(Error::MessageTooLong { .. })
+		));
+	}
+}
@@ -0,0 +1,182 @@
+//! Versioned hybrid envelope for timed release payloads of arbitrary length.
+//! A random secret smaller than the modulus is ElGamal encrypted to the TREX public key, the
+//! symmetric key derived from the secret encrypts the payload with ChaCha20-Poly1305.
+use crate::{elgamal, BlockNumber, Error};
+use chacha20poly1305::{
+	aead::{Aead, NewAead, Payload},
+	ChaCha20Poly1305, Key, Nonce,
+};
+use codec::{Decode, Encode};
+use elgamal_trex::elgamal::{PrivateKey, PublicKey};
+use rand::{CryptoRng, RngCore};
+use rug::{integer::Order, Integer};
+use sp_core::U256;
+use trex_pow::utils::{bigint_u256, u256_bigint};
+
+/// Context string of the key derivation function for symmetric keys.
+const KDF_CONTEXT: &str = "TREX timed release envelope symmetric key";
+
+/// A key secret encrypted to the public key released by a target block.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Debug)]
+pub struct KeyCapsule {
+	/// Number of the block releasing the private key.
+	pub target: BlockNumber,
+	/// Bit length of the key, which is the mining difficulty of the target block.
+	pub bit_length: u32,
+	/// The prime modulus of the key.
+	pub p: U256,
+	pub c1: U256,
+	pub c2: U256,
+}
+
+impl KeyCapsule {
+	/// Draw a random secret and encrypt it to a public key, return the capsule and the secret.
+	pub fn new<R: RngCore + CryptoRng>(
+		target: BlockNumber,
+		pubkey: &PublicKey,
+		rng: &mut R,
+	) -> Result<(Self, Integer), Error> {
+		// draw the secret from [1, p - 1].
+		let mut bytes = [0u8; 32];
+		rng.fill_bytes(&mut bytes);
+		let range = Integer::from(&pubkey.p - 1);
+		let secret = Integer::from_digits(&bytes, Order::Lsf) % range + 1;
+		let (c1, c2) = elgamal::encrypt(pubkey, &secret, rng)?;
+		let capsule = Self {
+			target,
+			bit_length: pubkey.bit_length,
+			p: bigint_u256(&pubkey.p),
+			c1: bigint_u256(&c1),
+			c2: bigint_u256(&c2),
+		};
+		Ok((capsule, secret))
+	}
+
+	/// Recover the secret with the released private key.
+	pub fn open(&self, key: &PrivateKey) -> Result<Integer, Error> {
+		if key.bit_length != self.bit_length || bigint_u256(&key.p) != self.p {
+			return Err(Error::KeyMismatch(self.target))
+		}
+		elgamal::decrypt(key, &u256_bigint(&self.c1), &u256_bigint(&self.c2))
+	}
+}
+
+/// Derive the symmetric key from the secrets of key capsules.
+pub fn derive_key(secrets: &[Integer]) -> [u8; 32] {
+	let mut hasher = blake3::Hasher::new_derive_key(KDF_CONTEXT);
+	for secret in secrets {
+		hasher.update(&bigint_u256(secret).encode());
+	}
+	*hasher.finalize().as_bytes()
+}
+
+/// The first version of envelope, encrypted to a single key.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Debug)]
+pub struct EnvelopeV1 {
+	pub capsule: KeyCapsule,
+	/// Nonce of the AEAD cipher.
+	pub nonce: [u8; 12],
+	/// Payload encrypted with the AEAD cipher, authenticated with the encoded capsule.
+	pub ciphertext: Vec<u8>,
+}
+
+/// A SCALE encoded timed release envelope, the variant index is the version of the format.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Debug)]
+pub enum Envelope {
+	#[codec(index = 1)]
+	V1(EnvelopeV1),
+}
+
+impl Envelope {
+	/// Seal a payload to the public key released by a target block.
+	pub fn seal<R: RngCore + CryptoRng>(
+		target: BlockNumber,
+		pubkey: &PublicKey,
+		payload: &[u8],
+		rng: &mut R,
+	) -> Result<Self, Error> {
+		let (capsule, secret) = KeyCapsule::new(target, pubkey, rng)?;
+		let mut nonce = [0u8; 12];
+		rng.fill_bytes(&mut nonce);
+		let ciphertext = aead_encrypt(&derive_key(&[secret]), &nonce, payload, &capsule.encode())?;
+		Ok(Envelope::V1(EnvelopeV1 { capsule, nonce, ciphertext }))
+	}
+
+	/// Numbers of the blocks whose released keys are needed to open this envelope.
+	pub fn targets(&self) -> Vec<BlockNumber> {
+		match self {
+			Envelope::V1(envelope) => vec![envelope.capsule.target],
+		}
+	}
+
+	/// Open the envelope with the private keys released by its target blocks, in order.
+	pub fn open(&self, keys: &[PrivateKey]) -> Result<Vec<u8>, Error> {
+		match self {
+			Envelope::V1(envelope) => {
+				let key = keys.first().ok_or(Error::KeyNotReleased(envelope.capsule.target))?;
+				let secret = envelope.capsule.open(key)?;
+				aead_decrypt(
+					&derive_key(&[secret]),
+					&envelope.nonce,
+					&envelope.ciphertext,
+					&envelope.capsule.encode(),
+				)
+			},
+		}
+	}
+}
+
+fn aead_encrypt(key: &[u8; 32], nonce: &[u8; 12], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+	ChaCha20Poly1305::new(Key::from_slice(key))
+		.encrypt(Nonce::from_slice(nonce), Payload { msg, aad })
+		.map_err(|_| Error::InvalidCiphertext)
+}
+
+fn aead_decrypt(key: &[u8; 32], nonce: &[u8; 12], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+	ChaCha20Poly1305::new(Key::from_slice(key))
+		.decrypt(Nonce::from_slice(nonce), Payload { msg, aad })
+		.map_err(|_| Error::InvalidCiphertext)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn test_key_pair(x: u64) -> (PublicKey, PrivateKey) {
+		let p = Integer::from(209805312383u64);
+		let g = Integer::from(38619647689u64);
+		let x = Integer::from(x);
+		let h = Integer::from(g.pow_mod_ref(&x, &p).unwrap());
+		(
+			PublicKey { p: p.clone(), g: g.clone(), h, bit_length: 38 },
+			PrivateKey { p, g, x, bit_length: 38 },
+		)
+	}
+
+	#[test]
+	fn seal_and_open_long_payload() {
+		let (pubkey, privkey) = test_key_pair(1234567);
+		let payload = vec![7u8; 1024];
+		let envelope = Envelope::seal(9, &pubkey, &payload, &mut rand::thread_rng()).unwrap();
+		assert_eq!(envelope.targets(), vec![9]);
+		let decoded = Envelope::decode(&mut &envelope.encode()[..]).unwrap();
+		assert_eq!(decoded.encode()[0], 1u8, "The first byte is the envelope version!");
+		assert_eq!(decoded.open(&[privkey]).unwrap(), payload);
+	}
+
+	#[test]
+	fn reject_wrong_key_and_tampering() {
+		let (pubkey, _) = test_key_pair(1234567);
+		let (_, wrong_key) = test_key_pair(7654321);
+		let envelope = Envelope::seal(9, &pubkey, b"secret", &mut rand::thread_rng()).unwrap();
+		assert!(matches!(envelope.open(&[wrong_key]), Err(Error::InvalidCiphertext)));
+
+		let (_, privkey) = test_key_pair(1234567);
+		let Envelope::V1(mut inner) = envelope;
+		inner.capsule.target = 10;
+		assert!(matches!(
+			Envelope::V1(inner).open(&[privkey]),
+			Err(Error::InvalidCiphertext)
+		));
+	}
+}
@@ -0,0 +1,41 @@
+use crate::BlockNumber;
+use trex_constants::Difficulty;
+
+/// Errors of the TREX client.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// Failed to query the node.
+	#[error("RPC request failed: {0}")]
+	Rpc(String),
+	/// The block carries no TREX seal.
+	#[error("Seal of block {0} is not found")]
+	SealNotFound(BlockNumber),
+	/// The public key of the block is not committed yet since its parent is not mined.
+	#[error("Public key of block {0} is not committed yet")]
+	KeyNotCommitted(BlockNumber),
+	/// The private key of the block is not released yet since it is not mined.
+	#[error("Private key of block {0} is not released yet")]
+	KeyNotReleased(BlockNumber),
+	/// The difficulty is out of the range covered by a keychain.
+	#[error("Difficulty {0} is out of keychain range")]
+	DifficultyOutOfRange(Difficulty),
+	/// The target block was mined at another difficulty, so the key of the ciphertext is never
+	/// released.
+	#[error("Block {block} is mined at difficulty {mined} rather than {expected}")]
+	DifficultyMismatch { block: BlockNumber, expected: Difficulty, mined: Difficulty },
+	/// The message cannot fit in the modulus of the key.
+	#[error("Message of {len} bytes is too long, at most {max} bytes are allowed")]
+	MessageTooLong { len: usize, max: usize },
+	/// The released key of the block does not match the key recorded in the envelope.
+	#[error("Released key of block {0} does not match the envelope")]
+	KeyMismatch(BlockNumber),
+	/// The ciphertext cannot be decrypted by the released key.
+	#[error("Invalid ciphertext")]
+	InvalidCiphertext,
+}
+
+impl From<jsonrpsee::core::Error> for Error {
+	fn from(err: jsonrpsee::core::Error) -> Self {
+		Error::Rpc(err.to_string())
+	}
+}
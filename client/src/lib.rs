@@ -0,0 +1,178 @@
+//! Client library for timed release encryption on TREX.
+//! A message is encrypted to the public key mined in a target block, and it is decrypted with
+//! the private key released once the target block is mined.
+pub mod elgamal;
+pub mod envelope;
+mod error;
+pub mod provider;
+
+pub use envelope::Envelope;
+pub use error::Error;
+pub use provider::{RpcSealProvider, SealProvider};
+
+use codec::{Decode, Encode};
+use elgamal_trex::elgamal::{PrivateKey, PublicKey};
+use sp_core::U256;
+use trex_constants::{Difficulty, MIN_DIFFICULTY};
+use trex_pow::utils::{bigint_u256, u256_bigint};
+
+/// An index to a block.
+pub type BlockNumber = u32;
+
+/// A message encrypted to the key released by a target block.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Debug)]
+pub struct TimedCiphertext {
+	/// Number of the block releasing the private key.
+	pub target: BlockNumber,
+	/// Bit length of the key, which is the mining difficulty of the target block.
+	pub bit_length: u32,
+	pub c1: U256,
+	pub c2: U256,
+}
+
+/// A client for timed release encryption with keys derived from block seals.
+pub struct TrexClient<P> {
+	provider: P,
+}
+
+impl<P: SealProvider + Sync> TrexClient<P> {
+	pub fn new(provider: P) -> Self {
+		Self { provider }
+	}
+
+	/// Get the number of the best block.
+	pub async fn best_number(&self) -> Result<BlockNumber, Error> {
+		self.provider.best_number().await
+	}
+
+	/// Get the public key which will be mined at a difficulty in the target block.
+	/// The key is committed by the seal of the parent block of the target.
+	pub async fn upcoming_public_key(
+		&self,
+		target: BlockNumber,
+		difficulty: Difficulty,
+	) -> Result<PublicKey, Error> {
+		let index = difficulty
+			.checked_sub(MIN_DIFFICULTY)
+			.ok_or(Error::DifficultyOutOfRange(difficulty))?;
+		let parent = target.checked_sub(1).ok_or(Error::KeyNotCommitted(target))?;
+		let seal = self.provider.seal_at(parent).await?.ok_or(Error::KeyNotCommitted(target))?;
+		seal.keychain()
+			.into_iter()
+			.nth(index as usize)
+			.ok_or(Error::DifficultyOutOfRange(difficulty))
+	}
+
+	/// Get the private key released by the target block.
+	pub async fn released_key(&self, target: BlockNumber) -> Result<PrivateKey, Error> {
+		if target == 0 {
+			return Err(Error::KeyNotReleased(target))
+		}
+		let seal = self.provider.seal_at(target).await?.ok_or(Error::KeyNotReleased(target))?;
+		seal.released_key().ok_or(Error::KeyNotReleased(target))
+	}
+
+	/// Encrypt a short message to the key mined at a difficulty in the target block.
+	pub async fn encrypt(
+		&self,
+		target: BlockNumber,
+		difficulty: Difficulty,
+		message: &[u8],
+	) -> Result<TimedCiphertext, Error> {
+		let pubkey = self.upcoming_public_key(target, difficulty).await?;
+		let max = elgamal::max_message_len(pubkey.bit_length);
+		if message.len() > max {
+			return Err(Error::MessageTooLong { len: message.len(), max })
+		}
+		let encoded = elgamal::encode_message(message);
+		let (c1, c2) = elgamal::encrypt(&pubkey, &encoded, &mut rand::thread_rng())?;
+		Ok(TimedCiphertext {
+			target,
+			bit_length: pubkey.bit_length,
+			c1: bigint_u256(&c1),
+			c2: bigint_u256(&c2),
+		})
+	}
+
+	/// Seal a payload of arbitrary length to the key mined at a difficulty in the target block.
+	pub async fn seal(
+		&self,
+		target: BlockNumber,
+		difficulty: Difficulty,
+		payload: &[u8],
+	) -> Result<Envelope, Error> {
+		let pubkey = self.upcoming_public_key(target, difficulty).await?;
+		Envelope::seal(target, &pubkey, payload, &mut rand::thread_rng())
+	}
+
+	/// Open an envelope once the keys of its target blocks are released.
+	pub async fn open(&self, envelope: &Envelope) -> Result<Vec<u8>, Error> {
+		let mut keys = Vec::new();
+		for target in envelope.targets() {
+			keys.push(self.released_key(target).await?);
+		}
+		envelope.open(&keys)
+	}
+
+	/// Decrypt a message once the key of its target block is released.
+	pub async fn decrypt(&self, ciphertext: &TimedCiphertext) -> Result<Vec<u8>, Error> {
+		let key = self.released_key(ciphertext.target).await?;
+		if key.bit_length != ciphertext.bit_length {
+			return Err(Error::DifficultyMismatch {
+				block: ciphertext.target,
+				expected: ciphertext.bit_length as Difficulty,
+				mined: key.bit_length as Difficulty,
+			})
+		}
+		let plain =
+			elgamal::decrypt(&key, &u256_bigint(&ciphertext.c1), &u256_bigint(&ciphertext.c2))?;
+		elgamal::decode_message(&plain).ok_or(Error::InvalidCiphertext)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use async_trait::async_trait;
+	use elgamal_trex::elgamal::RawKey;
+	use trex_constants::INIT_DIFFICULTY;
+	use trex_pow::{genesis::genesis_seal, Seal};
+
+	/// A provider of a chain with only the genesis block.
+	struct GenesisProvider;
+
+	#[async_trait]
+	impl SealProvider for GenesisProvider {
+		async fn best_number(&self) -> Result<BlockNumber, Error> {
+			Ok(0)
+		}
+
+		async fn seal_at(&self, number: BlockNumber) -> Result<Option<Seal>, Error> {
+			Ok(if number == 0 { Some(genesis_seal(INIT_DIFFICULTY)) } else { None })
+		}
+	}
+
+	#[test]
+	fn upcoming_key_follows_consensus_keychain() {
+		let client = TrexClient::new(GenesisProvider);
+		let pubkey =
+			futures::executor::block_on(client.upcoming_public_key(1, INIT_DIFFICULTY)).unwrap();
+		let expected = genesis_seal(INIT_DIFFICULTY).keychain()
+			[(INIT_DIFFICULTY - MIN_DIFFICULTY) as usize]
+			.to_raw();
+		assert_eq!(pubkey.to_raw(), expected);
+		// the key of block 2 is unknown until block 1 is mined.
+		assert!(matches!(
+			futures::executor::block_on(client.upcoming_public_key(2, INIT_DIFFICULTY)),
+			Err(Error::KeyNotCommitted(2))
+		));
+		assert!(matches!(
+			futures::executor::block_on(client.encrypt(1, INIT_DIFFICULTY, b"hello")),
+			Ok(TimedCiphertext { target: 1, .. })
+		));
+		assert!(matches!(
+			futures::executor::block_on(client.released_key(1)),
+			Err(Error::KeyNotReleased(1))
+		));
+	}
+}
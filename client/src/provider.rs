@@ -0,0 +1,58 @@
+//! Providers of block seals, from which the keychains and released keys are derived.
+use crate::{BlockNumber, Error};
+use async_trait::async_trait;
+use jsonrpsee::{
+	core::client::ClientT,
+	http_client::{HttpClient, HttpClientBuilder},
+	rpc_params,
+};
+use sp_core::H256;
+use sp_runtime::{generic, traits::BlakeTwo256};
+use trex_pow::{seal_from_header, Seal};
+
+/// Block header type of TREX chain.
+pub type Header = generic::Header<BlockNumber, BlakeTwo256>;
+
+/// Methods to fetch the seals of blocks in the best chain.
+#[async_trait]
+pub trait SealProvider {
+	/// Get the number of the best block.
+	async fn best_number(&self) -> Result<BlockNumber, Error>;
+	/// Get the seal of the block at a height, return none if the block is not mined yet.
+	async fn seal_at(&self, number: BlockNumber) -> Result<Option<Seal>, Error>;
+}
+
+/// A seal provider querying a TREX node through JSON RPC.
+pub struct RpcSealProvider {
+	client: HttpClient,
+}
+
+impl RpcSealProvider {
+	/// Create a provider connecting to the HTTP RPC endpoint of a node.
+	pub fn new(url: &str) -> Result<Self, Error> {
+		Ok(Self { client: HttpClientBuilder::default().build(url)? })
+	}
+}
+
+#[async_trait]
+impl SealProvider for RpcSealProvider {
+	async fn best_number(&self) -> Result<BlockNumber, Error> {
+		let header: Header = self.client.request("chain_getHeader", rpc_params![]).await?;
+		Ok(header.number)
+	}
+
+	async fn seal_at(&self, number: BlockNumber) -> Result<Option<Seal>, Error> {
+		let hash: Option<H256> =
+			self.client.request("chain_getBlockHash", rpc_params![number]).await?;
+		let hash = match hash {
+			Some(hash) => hash,
+			None => return Ok(None),
+		};
+		let header: Option<Header> =
+			self.client.request("chain_getHeader", rpc_params![hash]).await?;
+		match header {
+			Some(header) => seal_from_header(&header).map(Some).ok_or(Error::SealNotFound(number)),
+			None => Ok(None),
+		}
+	}
+}
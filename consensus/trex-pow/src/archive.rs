@@ -0,0 +1,448 @@
+//! Archive of the private keys released by mining blocks.
+//! Every imported seal reveals the private key of its public key, the key is derived on import
+//! and persisted in the aux store of the client, along with an index of the canonical chain.
+use crate::{seal_from_digest, utils::{bigint_u256, u256_bigint}};
+use codec::{Decode, Encode};
+use elgamal_trex::elgamal::{PrivateKey, PublicKey};
+use log::warn;
+use rug::Integer;
+use sc_client_api::AuxStore;
+use sc_consensus::{
+    BlockCheckParams, BlockImport, BlockImportParams, ForkChoiceStrategy, ImportResult,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::{well_known_cache_keys::Id as CacheKeyId, HeaderBackend};
+use sp_consensus::Error as ConsensusError;
+use sp_core::U256;
+use sp_runtime::{
+    generic::BlockId,
+    traits::{AtLeast32BitUnsigned, Block as BlockT, Header as HeaderT, NumberFor, One, Zero},
+};
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+/// Aux store prefix of released keys indexed by block hash.
+const RELEASED_KEY_PREFIX: &[u8] = b"trex_released_key";
+/// Aux store prefix of canonical block hashes indexed by block number.
+const CANONICAL_HASH_PREFIX: &[u8] = b"trex_canonical_hash";
+/// Aux store key of the best block number in the canonical index.
+const BEST_CANONICAL_KEY: &[u8] = b"trex_best_canonical";
+/// Aux store key of the lowest block number from which the canonical index is up to date.
+const CANONICAL_FLOOR_KEY: &[u8] = b"trex_canonical_floor";
+/// Maximum number of ancestors re-pointed in the canonical index by a single import.
+const MAX_CANONICAL_WALK: u32 = 4096;
+
+/// A private key released by mining a block.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Debug)]
+pub struct ReleasedKey<H, N> {
+    /// Hash of the block releasing the key.
+    pub hash: H,
+    /// Number of the block releasing the key.
+    pub number: N,
+    /// Bit length of the key, which is the mining difficulty of the block.
+    pub bit_length: u32,
+    pub p: U256,
+    pub g: U256,
+    pub x: U256,
+}
+
+impl<H, N> ReleasedKey<H, N> {
+    pub fn new(hash: H, number: N, key: &PrivateKey) -> Self {
+        Self {
+            hash,
+            number,
+            bit_length: key.bit_length,
+            p: bigint_u256(&key.p),
+            g: bigint_u256(&key.g),
+            x: bigint_u256(&key.x),
+        }
+    }
+
+    /// Convert the archived key back to a private key.
+    pub fn private_key(&self) -> PrivateKey {
+        PrivateKey {
+            p: u256_bigint(&self.p),
+            g: u256_bigint(&self.g),
+            x: u256_bigint(&self.x),
+            bit_length: self.bit_length,
+        }
+    }
+
+    /// Derive the public key of the archived key.
+    pub fn public_key(&self) -> PublicKey {
+        let key = self.private_key();
+        let h = Integer::from(key.g.pow_mod_ref(&key.x, &key.p).unwrap());
+        PublicKey { p: key.p, g: key.g, h, bit_length: key.bit_length }
+    }
+}
+
+fn released_key_aux_key<H: Encode>(hash: &H) -> Vec<u8> {
+    (RELEASED_KEY_PREFIX, hash).encode()
+}
+
+fn canonical_hash_aux_key<N: Encode>(number: &N) -> Vec<u8> {
+    (CANONICAL_HASH_PREFIX, number).encode()
+}
+
+fn load_decode<C: AuxStore, T: Decode>(client: &C, key: &[u8]) -> sp_blockchain::Result<Option<T>> {
+    match client.get_aux(key)? {
+        Some(bytes) => T::decode(&mut &bytes[..]).map(Some).map_err(|e| {
+            sp_blockchain::Error::Backend(format!("TREX key archive is corrupted: {}", e))
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Load the key released by a block, no matter whether the block is canonical.
+pub fn load_released_key<B: BlockT, C: AuxStore>(
+    client: &C,
+    hash: &B::Hash,
+) -> sp_blockchain::Result<Option<ReleasedKey<B::Hash, NumberFor<B>>>> {
+    load_decode(client, &released_key_aux_key(hash))
+}
+
+/// Load the hash of the canonical block at a height in the key archive. The heights below the
+/// floor of the index may point to a retracted chain, so they are reported as unindexed.
+pub fn load_canonical_hash<B: BlockT, C: AuxStore>(
+    client: &C,
+    number: NumberFor<B>,
+) -> sp_blockchain::Result<Option<B::Hash>> {
+    match load_decode::<C, NumberFor<B>>(client, CANONICAL_FLOOR_KEY)? {
+        Some(floor) if number < floor => Ok(None),
+        _ => load_decode(client, &canonical_hash_aux_key(&number)),
+    }
+}
+
+/// Load the key released by the canonical block at a height.
+pub fn load_canonical_key<B: BlockT, C: AuxStore>(
+    client: &C,
+    number: NumberFor<B>,
+) -> sp_blockchain::Result<Option<ReleasedKey<B::Hash, NumberFor<B>>>> {
+    match load_canonical_hash::<B, C>(client, number)? {
+        Some(hash) => load_released_key::<B, C>(client, &hash),
+        None => Ok(None),
+    }
+}
+
+/// Load the best block number in the canonical index of the key archive.
+pub fn load_best_canonical<B: BlockT, C: AuxStore>(
+    client: &C,
+) -> sp_blockchain::Result<Option<NumberFor<B>>> {
+    load_decode(client, BEST_CANONICAL_KEY)
+}
+
+/// Block import to derive and archive the private key released by each imported seal.
+/// It must be wrapped by a PoW block import, so that the seal is verified and the fork choice
+/// is made before the block reaches this layer.
+pub struct KeyArchiveBlockImport<B: BlockT, I, C> {
+    inner: I,
+    client: Arc<C>,
+    _marker: PhantomData<B>,
+}
+
+impl<B: BlockT, I: Clone, C> Clone for KeyArchiveBlockImport<B, I, C> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone(), client: self.client.clone(), _marker: PhantomData }
+    }
+}
+
+impl<B, I, C> KeyArchiveBlockImport<B, I, C>
+    where
+        B: BlockT,
+        C: HeaderBackend<B> + AuxStore,
+{
+    /// Create a new block import archiving released keys.
+    pub fn new(inner: I, client: Arc<C>) -> Self {
+        Self { inner, client, _marker: PhantomData }
+    }
+
+    /// Build the aux store operations to make the given block the head of canonical index.
+    fn canonicalize(
+        &self,
+        hash: B::Hash,
+        number: NumberFor<B>,
+        parent_hash: B::Hash,
+    ) -> sp_blockchain::Result<Vec<(Vec<u8>, Option<Vec<u8>>)>> {
+        let client = self.client.as_ref();
+        canonical_ops(
+            hash,
+            number,
+            parent_hash,
+            load_best_canonical::<B, C>(client)?,
+            |number| load_canonical_hash::<B, C>(client, number),
+            |hash| {
+                let header = client
+                    .header(BlockId::Hash(hash))?
+                    .ok_or_else(|| sp_blockchain::Error::UnknownBlock(format!("{:?}", hash)))?;
+                Ok(*header.parent_hash())
+            },
+        )
+    }
+}
+
+/// Build the aux store operations to make a block the head of the canonical index, given the best
+/// block number of the index, a lookup of the indexed hashes and a lookup of the parent hashes.
+/// The ancestors are re-pointed until the common ancestor with the old canonical chain, but at
+/// most `MAX_CANONICAL_WALK` of them, so the first import after an upgrade or a deeper reorg does
+/// not walk back to genesis. The index is then only up to date above the last re-pointed ancestor,
+/// which becomes the floor of the index.
+fn canonical_ops<H, N>(
+    hash: H,
+    number: N,
+    parent_hash: H,
+    best: Option<N>,
+    canonical_hash: impl Fn(N) -> sp_blockchain::Result<Option<H>>,
+    parent_of: impl Fn(H) -> sp_blockchain::Result<H>,
+) -> sp_blockchain::Result<Vec<(Vec<u8>, Option<Vec<u8>>)>>
+    where
+        H: Encode + PartialEq + Copy + std::fmt::Debug,
+        N: AtLeast32BitUnsigned + Encode + Copy,
+{
+    let mut ops = vec![(canonical_hash_aux_key(&number), Some(hash.encode()))];
+    // retract the entries of the old canonical chain above the new best block.
+    if let Some(best) = best {
+        let mut retracted = number + One::one();
+        while retracted <= best {
+            ops.push((canonical_hash_aux_key(&retracted), None));
+            retracted += One::one();
+        }
+    }
+    ops.push((BEST_CANONICAL_KEY.to_vec(), Some(number.encode())));
+    // re-point the ancestors until reaching the common ancestor with the old canonical chain.
+    let mut hash = parent_hash;
+    let mut number = number.saturating_sub(One::one());
+    let mut walked = 0u32;
+    while !number.is_zero() {
+        if canonical_hash(number)? == Some(hash) {
+            break
+        }
+        if walked == MAX_CANONICAL_WALK {
+            warn!("Stop indexing the canonical chain of the key archive below block {:?}", hash);
+            ops.push((CANONICAL_FLOOR_KEY.to_vec(), Some((number + One::one()).encode())));
+            break
+        }
+        ops.push((canonical_hash_aux_key(&number), Some(hash.encode())));
+        hash = parent_of(hash)?;
+        number -= One::one();
+        walked += 1;
+    }
+    Ok(ops)
+}
+
+#[async_trait::async_trait]
+impl<B, I, C> BlockImport<B> for KeyArchiveBlockImport<B, I, C>
+    where
+        B: BlockT,
+        I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync,
+        I::Error: Into<ConsensusError>,
+        C: ProvideRuntimeApi<B> + HeaderBackend<B> + AuxStore + Send + Sync + 'static,
+{
+    type Error = ConsensusError;
+    type Transaction = sp_api::TransactionFor<C, B>;
+
+    async fn check_block(
+        &mut self,
+        block: BlockCheckParams<B>,
+    ) -> Result<ImportResult, Self::Error> {
+        self.inner.check_block(block).await.map_err(Into::into)
+    }
+
+    async fn import_block(
+        &mut self,
+        mut block: BlockImportParams<B, Self::Transaction>,
+        new_cache: HashMap<CacheKeyId, Vec<u8>>,
+    ) -> Result<ImportResult, Self::Error> {
+        let hash = block.post_hash();
+        let number = *block.header.number();
+        if let Some(seal) = block.post_digests.last().and_then(seal_from_digest) {
+            match seal.released_key() {
+                Some(key) => {
+                    let released = ReleasedKey::new(hash, number, &key);
+                    block.auxiliary.push((released_key_aux_key(&hash), Some(released.encode())));
+                },
+                None => warn!("Failed to derive the released key of block {:?}!", hash),
+            }
+        }
+        // keep the canonical index consistent with the fork choice made by PoW block import.
+        if let Some(ForkChoiceStrategy::Custom(true)) = block.fork_choice {
+            let parent_hash = *block.header.parent_hash();
+            let ops = self
+                .canonicalize(hash, number, parent_hash)
+                .map_err(|e| ConsensusError::ClientImport(e.to_string()))?;
+            block.auxiliary.extend(ops);
+        }
+        self.inner
+            .import_block(block, new_cache)
+            .await
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::H256;
+    use std::collections::BTreeMap;
+
+    /// An in-memory aux store of the canonical index with the parents of the imported blocks.
+    #[derive(Default)]
+    struct Index {
+        aux: BTreeMap<Vec<u8>, Vec<u8>>,
+        parents: HashMap<H256, H256>,
+    }
+
+    impl Index {
+        /// Look up the index the way `load_canonical_hash` does.
+        fn canonical(&self, number: u64) -> Option<H256> {
+            if let Some(bytes) = self.aux.get(CANONICAL_FLOOR_KEY) {
+                if number < u64::decode(&mut &bytes[..]).unwrap() {
+                    return None
+                }
+            }
+            let bytes = self.aux.get(&canonical_hash_aux_key(&number))?;
+            Some(H256::decode(&mut &bytes[..]).unwrap())
+        }
+
+        fn best(&self) -> Option<u64> {
+            let bytes = self.aux.get(BEST_CANONICAL_KEY)?;
+            Some(u64::decode(&mut &bytes[..]).unwrap())
+        }
+
+        /// Import a block as the new best block.
+        fn import(&mut self, hash: H256, number: u64, parent_hash: H256) {
+            self.parents.insert(hash, parent_hash);
+            let ops = canonical_ops(
+                hash,
+                number,
+                parent_hash,
+                self.best(),
+                |number| Ok(self.canonical(number)),
+                |hash| Ok(self.parents[&hash]),
+            )
+            .unwrap();
+            for (key, value) in ops {
+                match value {
+                    Some(value) => self.aux.insert(key, value),
+                    None => self.aux.remove(&key),
+                };
+            }
+        }
+
+        /// Import a branch of blocks from a parent, the hashes are derived from a branch tag.
+        fn import_branch(&mut self, tag: u8, parent: (H256, u64), len: u64) -> Vec<H256> {
+            let (mut parent_hash, number) = parent;
+            (number + 1..=number + len)
+                .map(|number| {
+                    let hash = branch_hash(tag, number);
+                    self.import(hash, number, parent_hash);
+                    parent_hash = hash;
+                    hash
+                })
+                .collect()
+        }
+
+        fn chain(&self) -> Vec<H256> {
+            (1..=self.best().unwrap()).map(|number| self.canonical(number).unwrap()).collect()
+        }
+    }
+
+    fn branch_hash(tag: u8, number: u64) -> H256 {
+        let mut hash = H256::repeat_byte(tag);
+        hash.0[..8].copy_from_slice(&number.to_le_bytes());
+        hash
+    }
+
+    #[test]
+    fn canonical_index_follows_the_best_chain() {
+        let mut index = Index::default();
+        let genesis = H256::zero();
+        let a = index.import_branch(0xa, (genesis, 0), 5);
+        assert_eq!(index.chain(), a);
+
+        // a longer fork from block 2 re-points the ancestors down to the common ancestor.
+        let b = index.import_branch(0xb, (a[1], 2), 4);
+        assert_eq!(index.best(), Some(6));
+        assert_eq!(index.chain(), [&a[..2], &b[..]].concat());
+
+        // a reorg to a shorter fork retracts the entries above the new best block.
+        let c = index.import_branch(0xc, (a[0], 1), 1);
+        assert_eq!(index.best(), Some(2));
+        assert_eq!(index.chain(), vec![a[0], c[0]]);
+        assert_eq!(index.canonical(3), None);
+        assert_eq!(index.canonical(6), None);
+
+        // switching back to a known branch re-points it as well.
+        index.import(a[4], 5, a[3]);
+        assert_eq!(index.chain(), a);
+    }
+
+    #[test]
+    fn canonical_walk_is_bounded() {
+        let mut parents = HashMap::new();
+        let mut hash = H256::zero();
+        let head = MAX_CANONICAL_WALK as u64 + 10;
+        for number in 1..=head {
+            let child = H256::from_low_u64_be(number);
+            parents.insert(child, hash);
+            hash = child;
+        }
+        // the first import after an upgrade finds an empty index.
+        let parent_hash = parents[&hash];
+        let ops =
+            canonical_ops(hash, head, parent_hash, None, |_| Ok(None), |hash| Ok(parents[&hash]))
+                .unwrap();
+        // the head, the best number, at most `MAX_CANONICAL_WALK` ancestors and the floor are
+        // indexed.
+        assert_eq!(ops.len(), MAX_CANONICAL_WALK as usize + 3);
+        assert!(!ops.iter().any(|(key, _)| key == &canonical_hash_aux_key(&9u64)));
+        assert!(ops.iter().any(|(key, _)| key == &canonical_hash_aux_key(&10u64)));
+        assert!(ops.contains(&(CANONICAL_FLOOR_KEY.to_vec(), Some(10u64.encode()))));
+    }
+
+    #[test]
+    fn deep_reorg_invalidates_the_stale_entries() {
+        let mut index = Index::default();
+        let genesis = H256::zero();
+        let len = MAX_CANONICAL_WALK as u64 + 10;
+        let a = index.import_branch(0xa, (genesis, 0), len);
+        assert_eq!(index.chain(), a);
+
+        // a longer fork from genesis is received, and only its head is imported as the best block.
+        let mut parent_hash = genesis;
+        for number in 1..=len {
+            index.parents.insert(branch_hash(0xb, number), parent_hash);
+            parent_hash = branch_hash(0xb, number);
+        }
+        index.import(branch_hash(0xb, len + 1), len + 1, parent_hash);
+
+        // `MAX_CANONICAL_WALK` ancestors are re-pointed, the stale entries below them are not
+        // served any more.
+        let floor = len + 1 - MAX_CANONICAL_WALK as u64;
+        assert_eq!(index.canonical(len + 1), Some(branch_hash(0xb, len + 1)));
+        assert_eq!(index.canonical(floor), Some(branch_hash(0xb, floor)));
+        assert_eq!(index.canonical(floor - 1), None);
+        assert_eq!(index.canonical(1), None);
+
+        // the next block of the fork does not touch the floor.
+        index.import(branch_hash(0xb, len + 2), len + 2, branch_hash(0xb, len + 1));
+        assert_eq!(index.canonical(floor), Some(branch_hash(0xb, floor)));
+        assert_eq!(index.canonical(floor - 1), None);
+    }
+
+    #[test]
+    fn released_key_round_trip() {
+        let key = PrivateKey {
+            p: Integer::from(209805312383u64),
+            g: Integer::from(38619647689u64),
+            x: Integer::from(1234567u64),
+            bit_length: 39,
+        };
+        let released = ReleasedKey::new(H256::from([1u8; 32]), 7u32, &key);
+        let decoded =
+            ReleasedKey::<H256, u32>::decode(&mut &released.encode()[..]).unwrap();
+        assert_eq!(decoded, released);
+        let restored = decoded.private_key();
+        assert_eq!((restored.p, restored.g, restored.x), (key.p, key.g, key.x));
+        assert_eq!(restored.bit_length, key.bit_length);
+        assert_eq!(decoded.public_key().h, Integer::from(79322574915u64));
+    }
+}
@@ -0,0 +1,324 @@
+//! Keychain digest of the mined blocks.
+//! The seal is a post-runtime digest, so the miner also puts the public key of the block and a
+//! commitment to its seeds in a TREX pre-runtime digest for the runtime. The proposer factory
+//! builds the digest from the parent seal, and the block import checks it matches the seal from
+//! `KEYCHAIN_DIGEST_HEIGHT` on.
+use crate::{
+    keychain::{yield_seeds, Keychain, KeychainCache, RawKeySeeds},
+    seal_from_digest, seal_from_header, Seal,
+};
+use codec::Encode;
+use elgamal_trex::elgamal::RawPublicKey;
+use sc_consensus::{BlockCheckParams, BlockImport, BlockImportParams, ImportResult};
+use sc_consensus_pow::PowAlgorithm;
+use sp_blockchain::well_known_cache_keys::Id as CacheKeyId;
+use sp_consensus::{Environment, Error as ConsensusError, Proposer};
+use sp_inherents::InherentData;
+use sp_runtime::{
+    traits::{Block as BlockT, Header as HeaderT, NumberFor},
+    Digest, DigestItem,
+};
+use std::{
+    collections::HashMap, future::Future, marker::PhantomData, pin::Pin, time::Duration,
+};
+use trex_constants::{Difficulty, MIN_DIFFICULTY};
+pub use trex_keychain::{KeychainDigest, TREX_ENGINE_ID};
+
+/// Reasons for rejecting the keychain digest of a block.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DigestError {
+    /// The block has no keychain digest.
+    Missing,
+    /// The keychain digest of the block does not match its seal.
+    Mismatch,
+    /// The block has no seal.
+    MissingSeal,
+    /// The seal of the block can not be decoded.
+    InvalidSeal,
+}
+
+/// Build the keychain digest of a public key and the seeds of the next keychain.
+pub fn keychain_digest(pubkey: &RawPublicKey, seeds: &RawKeySeeds) -> KeychainDigest {
+    let pubkey = trex_keychain::RawPublicKey {
+        bit_length: pubkey.bit_length,
+        p: pubkey.p,
+        g: pubkey.g,
+        h: pubkey.h,
+    };
+    KeychainDigest::new(pubkey, &seeds.encode())
+}
+
+/// The keychain digest committed by a keychain for a child block mined at a difficulty.
+pub fn child_digest_of(keychain: &Keychain, difficulty: Difficulty) -> Option<KeychainDigest> {
+    let index = difficulty.checked_sub(MIN_DIFFICULTY)?;
+    let pubkey = keychain.get(index as usize)?.to_raw();
+    Some(keychain_digest(&pubkey, &yield_seeds(keychain)))
+}
+
+/// Verify the keychain digest of a block header matches the seal of the block.
+pub fn verify_digest<H: HeaderT>(header: &H, seal: &Seal) -> Result<(), DigestError> {
+    match KeychainDigest::find(header.digest()) {
+        Some(digest) if digest == seal.digest() => Ok(()),
+        Some(_) => Err(DigestError::Mismatch),
+        None => Err(DigestError::Missing),
+    }
+}
+
+/// Check the seal and the keychain digest of an imported block, whose last post-runtime digest is
+/// the seal. Blocks below the activation height are imported without them.
+pub fn check_import<H: HeaderT>(
+    header: &H,
+    seal: Option<&DigestItem>,
+    activation: H::Number,
+) -> Result<(), DigestError> {
+    if *header.number() < activation {
+        return Ok(())
+    }
+    let seal = seal.ok_or(DigestError::MissingSeal)?;
+    let seal = seal_from_digest(seal).ok_or(DigestError::InvalidSeal)?;
+    verify_digest(header, &seal)
+}
+
+/// Proposer factory putting the keychain digest in the pre-runtime digests of the new blocks.
+pub struct KeychainProposerFactory<B: BlockT, E, A> {
+    inner: E,
+    algorithm: A,
+    cache: KeychainCache<B::Hash>,
+    _marker: PhantomData<B>,
+}
+
+impl<B: BlockT, E, A> KeychainProposerFactory<B, E, A> {
+    /// Create a new proposer factory wrapping an inner one.
+    pub fn new(inner: E, algorithm: A) -> Self {
+        Self { inner, algorithm, cache: KeychainCache::default(), _marker: PhantomData }
+    }
+}
+
+impl<B, E, A> KeychainProposerFactory<B, E, A>
+    where
+        B: BlockT,
+        A: PowAlgorithm<B, Difficulty = Difficulty>,
+{
+    /// Build the keychain digest of a child block, which is committed by the parent seal for the
+    /// difficulty of the child. The keychain of the parent is derived once for all the proposals
+    /// on the same parent.
+    fn child_digest(&self, parent: &B::Header) -> Result<DigestItem, ConsensusError> {
+        let seal = seal_from_header(parent).ok_or_else(|| {
+            ConsensusError::ChainLookup(format!("No seal in block {:?}", parent.hash()))
+        })?;
+        let difficulty = self
+            .algorithm
+            .difficulty(parent.hash())
+            .map_err(|e| ConsensusError::ChainLookup(e.to_string()))?;
+        let keychain = self.cache.get_or_derive(&parent.hash(), || seal.keychain());
+        let digest = child_digest_of(&keychain, difficulty).ok_or_else(|| {
+            ConsensusError::ChainLookup(format!("Difficulty {} out of the keychain", difficulty))
+        })?;
+        Ok(DigestItem::PreRuntime(TREX_ENGINE_ID, digest.encode()))
+    }
+}
+
+impl<B, E, A> Environment<B> for KeychainProposerFactory<B, E, A>
+    where
+        B: BlockT,
+        E: Environment<B>,
+        A: PowAlgorithm<B, Difficulty = Difficulty>,
+{
+    type Proposer = KeychainProposer<B, E::Proposer>;
+    type CreateProposer =
+        Pin<Box<dyn Future<Output = Result<Self::Proposer, Self::Error>> + Send>>;
+    type Error = E::Error;
+
+    fn init(&mut self, parent_header: &B::Header) -> Self::CreateProposer {
+        let digest = match self.child_digest(parent_header) {
+            Ok(digest) => digest,
+            Err(e) => return Box::pin(std::future::ready(Err(e.into()))),
+        };
+        let inner = self.inner.init(parent_header);
+        Box::pin(async move {
+            Ok(KeychainProposer { inner: inner.await?, digest, _marker: PhantomData })
+        })
+    }
+}
+
+/// Proposer pushing the keychain digest of the block into its inherent digests.
+pub struct KeychainProposer<B: BlockT, P> {
+    inner: P,
+    digest: DigestItem,
+    _marker: PhantomData<B>,
+}
+
+impl<B, P> Proposer<B> for KeychainProposer<B, P>
+    where
+        B: BlockT,
+        P: Proposer<B>,
+{
+    type Error = P::Error;
+    type Transaction = P::Transaction;
+    type Proposal = P::Proposal;
+    type ProofRecording = P::ProofRecording;
+    type Proof = P::Proof;
+
+    fn propose(
+        self,
+        inherent_data: InherentData,
+        mut inherent_digests: Digest,
+        max_duration: Duration,
+        block_size_limit: Option<usize>,
+    ) -> Self::Proposal {
+        inherent_digests.push(self.digest);
+        self.inner.propose(inherent_data, inherent_digests, max_duration, block_size_limit)
+    }
+}
+
+/// Block import to reject the blocks without a seal, or whose keychain digest does not match their
+/// seal, from the activation height on.
+/// It must be wrapped by a PoW block import, so that the seal is verified against the parent seal
+/// before the block reaches this layer.
+pub struct KeychainBlockImport<B: BlockT, I> {
+    inner: I,
+    activation: NumberFor<B>,
+    _marker: PhantomData<B>,
+}
+
+impl<B: BlockT, I: Clone> Clone for KeychainBlockImport<B, I> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone(), activation: self.activation, _marker: PhantomData }
+    }
+}
+
+impl<B: BlockT, I> KeychainBlockImport<B, I> {
+    /// Create a new block import verifying keychain digests of the blocks from `activation` on.
+    pub fn new(inner: I, activation: NumberFor<B>) -> Self {
+        Self { inner, activation, _marker: PhantomData }
+    }
+}
+
+#[async_trait::async_trait]
+impl<B, I> BlockImport<B> for KeychainBlockImport<B, I>
+    where
+        B: BlockT,
+        I: BlockImport<B> + Send + Sync,
+        I::Error: Into<ConsensusError>,
+        I::Transaction: Send + 'static,
+{
+    type Error = ConsensusError;
+    type Transaction = I::Transaction;
+
+    async fn check_block(
+        &mut self,
+        block: BlockCheckParams<B>,
+    ) -> Result<ImportResult, Self::Error> {
+        self.inner.check_block(block).await.map_err(Into::into)
+    }
+
+    async fn import_block(
+        &mut self,
+        block: BlockImportParams<B, Self::Transaction>,
+        new_cache: HashMap<CacheKeyId, Vec<u8>>,
+    ) -> Result<ImportResult, Self::Error> {
+        check_import(&block.header, block.post_digests.last(), self.activation).map_err(|e| {
+            ConsensusError::ClientImport(format!(
+                "Invalid keychain digest in block {:?}: {:?}",
+                block.post_hash(),
+                e
+            ))
+        })?;
+        self.inner
+            .import_block(block, new_cache)
+            .await
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genesis::genesis_seal;
+    use sp_consensus_pow::POW_ENGINE_ID;
+    use sp_core::H256;
+    use sp_runtime::{generic::Header, traits::BlakeTwo256};
+    use trex_constants::{INIT_DIFFICULTY, KEYCHAIN_DIGEST_HEIGHT, MIN_DIFFICULTY};
+
+    fn header_with(logs: Vec<DigestItem>) -> Header<u32, BlakeTwo256> {
+        header_at(1, logs)
+    }
+
+    fn header_at(number: u32, logs: Vec<DigestItem>) -> Header<u32, BlakeTwo256> {
+        Header::new(number, H256::zero(), H256::zero(), H256::zero(), Digest { logs })
+    }
+
+    fn seal_item(seal: &Seal) -> DigestItem {
+        DigestItem::Seal(POW_ENGINE_ID, seal.encode())
+    }
+
+    #[test]
+    fn child_digest_matches_the_child_seal() {
+        let parent = genesis_seal(INIT_DIFFICULTY);
+        let keychain = parent.keychain();
+        let mut child = genesis_seal(INIT_DIFFICULTY);
+        child.pubkey = keychain[(INIT_DIFFICULTY - MIN_DIFFICULTY) as usize].to_raw();
+        child.seeds = yield_seeds(&keychain);
+
+        // the digest is known from the parent seal before the child is mined.
+        let digest = parent.child_digest(INIT_DIFFICULTY).unwrap();
+        assert_eq!(digest, child.digest());
+        assert_ne!(digest, parent.child_digest(INIT_DIFFICULTY + 1).unwrap());
+        assert_eq!(parent.child_digest(MIN_DIFFICULTY - 1), None);
+        assert_eq!(child_digest_of(&keychain, INIT_DIFFICULTY), Some(digest.clone()));
+
+        let header = header_with(vec![DigestItem::PreRuntime(TREX_ENGINE_ID, digest.encode())]);
+        assert_eq!(KeychainDigest::find(header.digest()), Some(digest));
+        assert_eq!(verify_digest(&header, &child), Ok(()));
+    }
+
+    #[test]
+    fn verify_digest_rejects_other_seals() {
+        let seal = genesis_seal(INIT_DIFFICULTY);
+        assert_eq!(verify_digest(&header_with(vec![]), &seal), Err(DigestError::Missing));
+
+        // the digest of the author with the PoW engine id is not a keychain digest.
+        let author = DigestItem::PreRuntime(POW_ENGINE_ID, vec![1u8; 32]);
+        assert_eq!(verify_digest(&header_with(vec![author]), &seal), Err(DigestError::Missing));
+
+        let mut other = seal.clone();
+        other.pubkey.h = other.pubkey.h + 1;
+        let item = DigestItem::PreRuntime(TREX_ENGINE_ID, other.digest().encode());
+        assert_eq!(verify_digest(&header_with(vec![item]), &seal), Err(DigestError::Mismatch));
+    }
+
+    #[test]
+    fn check_import_requires_the_seal_from_the_activation() {
+        let seal = genesis_seal(INIT_DIFFICULTY);
+        let item = DigestItem::PreRuntime(TREX_ENGINE_ID, seal.digest().encode());
+        let header = header_with(vec![item]);
+        assert_eq!(check_import(&header, Some(&seal_item(&seal)), 1), Ok(()));
+        assert_eq!(check_import(&header, None, 1), Err(DigestError::MissingSeal));
+        let garbage = DigestItem::Seal(POW_ENGINE_ID, vec![1u8; 3]);
+        assert_eq!(check_import(&header, Some(&garbage), 1), Err(DigestError::InvalidSeal));
+        let unsealed = header_with(vec![]);
+        assert_eq!(check_import(&unsealed, Some(&seal_item(&seal)), 1), Err(DigestError::Missing));
+
+        // the blocks below the activation height are imported without the digest.
+        assert_eq!(check_import(&unsealed, None, 2), Ok(()));
+    }
+
+    #[test]
+    fn check_import_around_the_fork_height() {
+        let seal = genesis_seal(INIT_DIFFICULTY);
+        let item = DigestItem::PreRuntime(TREX_ENGINE_ID, seal.digest().encode());
+
+        // the blocks mined before the fork carry neither the digest nor a decodable seal.
+        let before = header_at(KEYCHAIN_DIGEST_HEIGHT - 1, vec![]);
+        assert_eq!(check_import(&before, None, KEYCHAIN_DIGEST_HEIGHT), Ok(()));
+
+        let at = header_at(KEYCHAIN_DIGEST_HEIGHT, vec![]);
+        assert_eq!(check_import(&at, None, KEYCHAIN_DIGEST_HEIGHT), Err(DigestError::MissingSeal));
+        assert_eq!(
+            check_import(&at, Some(&seal_item(&seal)), KEYCHAIN_DIGEST_HEIGHT),
+            Err(DigestError::Missing)
+        );
+        let sealed = header_at(KEYCHAIN_DIGEST_HEIGHT, vec![item]);
+        assert_eq!(check_import(&sealed, Some(&seal_item(&seal)), KEYCHAIN_DIGEST_HEIGHT), Ok(()));
+    }
+}
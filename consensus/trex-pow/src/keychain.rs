@@ -1,9 +1,10 @@
 use trex_constants::{MAX_DIFFICULTY, MIN_DIFFICULTY};
-use elgamal_trex::{elgamal::PublicKey, generate_pub_key, utils::u256_bigint,};
-use crate::utils::u128_bigint;
-use rug::rand::RandState;
+use elgamal_trex::{elgamal::PublicKey, generate_pub_key, Seed};
+use crate::utils::{bigint_u128, bigint_u256};
+use rug::{integer::Order, rand::RandState, Integer};
 use sp_core::U256;
 use codec::{Decode, Encode};
+use std::sync::{Arc, Mutex};
 
 /// The raw form of integer as seeds to derive a chain of public keys.
 pub type RawKeySeeds = [RawKeySeedsData; (MAX_DIFFICULTY - MIN_DIFFICULTY) as usize];
@@ -16,18 +17,69 @@ pub enum RawKeySeedsData{
 pub type Keychain = Vec<PublicKey>;
 
 /// Yield a list of new public keys from seeds generated from public keys in previous block.
-pub fn yield_pub_keys(seeds: RawKeySeeds) -> Keychain {
+/// Every seed is mixed with the entropy of the block sealing it, so the keychain cannot be
+/// derived until that block is sealed.
+pub fn yield_pub_keys(seeds: RawKeySeeds, entropy: &[u8]) -> Keychain {
 	seeds.iter().enumerate().map(|(index, u_seed)| {
 		let mut rand = RandState::new_mersenne_twister();
-		let seed= match u_seed {
-			RawKeySeedsData::U128(value) => {
-				u128_bigint(value)
-			},
-			RawKeySeedsData::U256(value) =>{
-				u256_bigint(value)
-			},
-		};
+		let seed = mix_seed(u_seed, entropy);
 		let bit_length = (index + MIN_DIFFICULTY as usize) as u32;
 		generate_pub_key(&mut rand, bit_length, seed)
 	}).collect()
 }
+
+/// Mix a raw seed with block entropy by hashing them together.
+fn mix_seed(u_seed: &RawKeySeedsData, entropy: &[u8]) -> Integer {
+	let mut hasher = blake3::Hasher::new();
+	hasher.update(&u_seed.encode());
+	hasher.update(entropy);
+	let seed = Integer::from_digits(hasher.finalize().as_bytes(), Order::Lsf);
+	// NOTE: never use 0 to initialize integers which may lead to memory corruption.
+	if seed == 0 {
+		Integer::from(1)
+	} else {
+		seed
+	}
+}
+
+/// Yield the seeds for the keychain of next block from the public keys in current keychain.
+pub fn yield_seeds(keychain: &Keychain) -> RawKeySeeds {
+	let mut seeds: RawKeySeeds =
+		[RawKeySeedsData::U128(1u128); (MAX_DIFFICULTY - MIN_DIFFICULTY) as usize];
+	for (idx, key) in keychain.iter().enumerate() {
+		if idx < (128 - MIN_DIFFICULTY) as usize {
+			seeds[idx] = RawKeySeedsData::U128(bigint_u128(&key.yield_seed()));
+		} else {
+			seeds[idx] = RawKeySeedsData::U256(bigint_u256(&key.yield_seed()));
+		}
+	}
+	seeds
+}
+
+/// Cache of the keychain committed by the last queried block.
+/// Deriving a keychain generates a prime for every difficulty, so the callers querying the keychain
+/// of the same best block over and over share a single derivation.
+pub struct KeychainCache<H> {
+	last: Mutex<Option<(H, Arc<Keychain>)>>,
+}
+
+impl<H> Default for KeychainCache<H> {
+	fn default() -> Self {
+		Self { last: Mutex::new(None) }
+	}
+}
+
+impl<H: PartialEq + Clone> KeychainCache<H> {
+	/// Get the keychain committed by a block, it is derived only if the block is not cached.
+	pub fn get_or_derive(&self, hash: &H, derive: impl FnOnce() -> Keychain) -> Arc<Keychain> {
+		let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+		match last.as_ref() {
+			Some((cached, keychain)) if cached == hash => keychain.clone(),
+			_ => {
+				let keychain = Arc::new(derive());
+				*last = Some((hash.clone(), keychain.clone()));
+				keychain
+			},
+		}
+	}
+}
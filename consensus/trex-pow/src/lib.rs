@@ -1,35 +1,39 @@
 pub mod algorithm;
+pub mod archive;
+pub mod digest;
 pub mod distributed;
 pub mod generic;
 pub mod genesis;
 pub mod hash;
-mod keychain;
+pub mod keychain;
 pub mod utils;
 
 use codec::{Decode, Encode};
-use elgamal_trex::{
-	elgamal::{PrivateKey, PublicKey, RawKey, RawPublicKey},
-	Seed,
-};
+use elgamal_trex::elgamal::{PrivateKey, PublicKey, RawKey, RawPublicKey};
 use log::{info, warn};
 use rug::{rand::RandState, Complete, Integer};
 use sc_client_api::{backend::AuxStore, blockchain::HeaderBackend};
 use sc_consensus_pow::{Error, PowAlgorithm};
 use sp_api::ProvideRuntimeApi;
-use sp_consensus_pow::{DifficultyApi, Seal as RawSeal};
+use sp_consensus_pow::{DifficultyApi, Seal as RawSeal, POW_ENGINE_ID};
 use sp_core::{H256, U256};
-use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, One, Zero},
+	DigestItem,
+};
 use std::sync::Arc;
-use trex_constants::{Difficulty, INIT_DIFFICULTY, MAX_DIFFICULTY, MIN_DIFFICULTY};
+use trex_constants::{Difficulty, INIT_DIFFICULTY, MIN_DIFFICULTY};
+use trex_keychain::KeychainDigest;
 
 // local packages.
 pub use crate::generic::{
 	CycleFinding, Hash, MapResult, Mapping, MappingError, Solution, Solutions, State,
 };
-use crate::{generic::StateHash, keychain::RawKeySeedsData, utils::bigint_u128};
+use crate::generic::StateHash;
 use algorithm::PollardRhoHash;
 pub use hash::Blake3Compute as Compute;
-use keychain::{yield_pub_keys, RawKeySeeds};
+use keychain::{yield_pub_keys, yield_seeds, Keychain, KeychainCache, RawKeySeeds};
 use std::sync::atomic::AtomicBool;
 use utils::{bigint_u256, gen_bigint_range, u256_bigint};
 
@@ -52,7 +56,8 @@ pub struct Seal {
 	pub difficulty: Difficulty,
 	/// The public key being mined in current block.
 	pub pubkey: RawPublicKey,
-	/// A list of seeds to derive the public keys for next blocks.
+	/// A list of seeds to derive the public keys for next blocks, which are mixed with the entropy
+	/// of this seal.
 	pub seeds: RawKeySeeds,
 	/// A pair of solution for current time-lock puzzle found by mining current block.
 	pub solutions: Solutions<U256>,
@@ -60,7 +65,55 @@ pub struct Seal {
 	pub nonce: U256,
 }
 
+/// Reasons for rejecting a seal which does not follow the keychain committed by its parent.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum KeychainError {
+	/// The difficulty of the seal is out of the range covered by a keychain.
+	DifficultyOutOfRange(Difficulty),
+	/// The public key in the seal is not derived from the seeds of the parent seal.
+	PubkeyMismatch,
+	/// The seed at this index of the seal is not derived from the keychain of the parent seal.
+	SeedMismatch(usize),
+}
+
 impl Seal {
+	/// Derive the keychain committed by this seal, the public key of the next block is one of them.
+	pub fn keychain(&self) -> Keychain {
+		yield_pub_keys(self.seeds.clone(), &self.entropy())
+	}
+
+	/// The entropy of this seal which is unknown until the block is sealed.
+	pub fn entropy(&self) -> [u8; 32] {
+		let data = (&self.solutions, &self.nonce).encode();
+		*blake3::hash(&data).as_bytes()
+	}
+
+	/// Verify that the public key and the seeds of a child seal are the ones committed by this seal.
+	pub fn verify_child(&self, child: &Seal) -> Result<(), KeychainError> {
+		verify_child_of(&self.keychain(), child)
+	}
+
+	/// The keychain digest of the block sealed by this seal.
+	pub fn digest(&self) -> KeychainDigest {
+		digest::keychain_digest(&self.pubkey, &self.seeds)
+	}
+
+	/// The keychain digest committed by this seal for a child block mined at a difficulty, which is
+	/// known before the child is mined.
+	pub fn child_digest(&self, difficulty: Difficulty) -> Option<KeychainDigest> {
+		digest::child_digest_of(&self.keychain(), difficulty)
+	}
+
+	/// Derive the private key released by mining the block sealed by this seal.
+	pub fn released_key(&self) -> Option<PrivateKey> {
+		let verifier = SolutionVerifier { pubkey: PublicKey::from_raw(self.pubkey.clone()) };
+		let solutions = (
+			Solution::<Integer>::from_u256(&self.solutions.0),
+			Solution::<Integer>::from_u256(&self.solutions.1),
+		);
+		verifier.key_gen(&solutions)
+	}
+
 	pub fn try_cpu_mining<
 		C: Clone
 			+ Hash<Integer, U256>
@@ -76,17 +129,9 @@ impl Seal {
 		cpus: Option<u8>,
 	) -> Option<Self> {
 		let difficulty = compute.get_difficulty();
-		let keychain = yield_pub_keys(self.seeds.clone());
+		let keychain = self.keychain();
 		let new_pubkey = keychain[(difficulty - MIN_DIFFICULTY) as usize].clone();
-		let mut new_seeds: RawKeySeeds =
-			[RawKeySeedsData::U128(1u128); (MAX_DIFFICULTY - MIN_DIFFICULTY) as usize];
-		for (idx, key) in keychain.into_iter().enumerate() {
-			if idx < (128 - MIN_DIFFICULTY) as usize {
-				new_seeds[idx] = RawKeySeedsData::U128(bigint_u128(&key.yield_seed()));
-			} else {
-				new_seeds[idx] = RawKeySeedsData::U256(bigint_u256(&key.yield_seed()));
-			}
-		}
+		let new_seeds = yield_seeds(&keychain);
 		let puzzle = new_pubkey.clone();
 		let seed = u256_bigint(&mining_seed);
 		if let Some(solutions) = match cpus {
@@ -115,6 +160,69 @@ impl Seal {
 	}
 }
 
+/// Verify that the public key and the seeds of a child seal are the ones committed by the keychain
+/// of its parent.
+pub fn verify_child_of(keychain: &Keychain, child: &Seal) -> Result<(), KeychainError> {
+	let index = child
+		.difficulty
+		.checked_sub(MIN_DIFFICULTY)
+		.ok_or(KeychainError::DifficultyOutOfRange(child.difficulty))?;
+	let pubkey = keychain
+		.get(index as usize)
+		.ok_or(KeychainError::DifficultyOutOfRange(child.difficulty))?;
+	if pubkey.to_raw() != child.pubkey {
+		return Err(KeychainError::PubkeyMismatch)
+	}
+	let seeds = yield_seeds(keychain);
+	if let Some(index) = seeds.iter().zip(child.seeds.iter()).position(|(a, b)| a != b) {
+		return Err(KeychainError::SeedMismatch(index))
+	}
+	Ok(())
+}
+
+/// Extract the TREX seal from a block header.
+/// The genesis block is not sealed, so an artificial genesis seal is returned for it.
+pub fn seal_from_header<H: HeaderT>(header: &H) -> Option<Seal> {
+	if header.number().is_zero() {
+		return Some(genesis::genesis_seal(INIT_DIFFICULTY))
+	}
+	header.digest().logs().iter().rev().find_map(seal_from_digest)
+}
+
+/// Decode the TREX seal from a digest item if it is a PoW seal.
+pub fn seal_from_digest(item: &DigestItem) -> Option<Seal> {
+	match item.as_seal() {
+		Some((id, mut data)) if id == POW_ENGINE_ID => Seal::decode(&mut data).ok(),
+		_ => None,
+	}
+}
+
+/// Verify a seal follows the keychain committed by the seal of its parent, which is required from
+/// the activation height on. The keychain of the parent is derived once for all its children.
+fn verify_keychain<B: BlockT<Hash = H256>, C: HeaderBackend<B>>(
+	client: &C,
+	keychains: &KeychainCache<H256>,
+	activation: u32,
+	parent: &BlockId<B>,
+	seal: &Seal,
+) -> Result<bool, Error<B>> {
+	let header = client
+		.header(*parent)
+		.map_err(Error::Client)?
+		.ok_or_else(|| Error::Environment(format!("Header of block {:?} is not found", parent)))?;
+	if *header.number() + One::one() < NumberFor::<B>::from(activation) {
+		return Ok(true)
+	}
+	let parent_seal = seal_from_header(&header)
+		.ok_or_else(|| Error::Environment(format!("Seal of block {:?} is not found", parent)))?;
+	let keychain = keychains.get_or_derive(&header.hash(), || parent_seal.keychain());
+	if let Err(err) = verify_child_of(&keychain, seal) {
+		warn!("The seal is rejected by the keychain of parent block: {:?}", err);
+		return Ok(false)
+	}
+	Ok(true)
+}
+
 /// Determine whether the given hash satisfies the given difficulty.
 /// The test is done by multiplying the two together. If the product
 /// overflows the bounds of U128, then the product (and thus the hash)
@@ -245,6 +353,12 @@ impl SolutionVerifier {
 			let new_key: Integer;
 			if validate != self.pubkey.h {
 				new_key = key + &solutions.1.n;
+				// the solutions may solve the equations without revealing the discrete logarithm.
+				let validate =
+					Integer::from(generator.pow_mod_ref(&new_key, &self.pubkey.p).unwrap());
+				if validate != self.pubkey.h {
+					return None
+				}
 			} else {
 				new_key = key;
 			}
@@ -260,13 +374,57 @@ impl SolutionVerifier {
 	}
 }
 
+/// Verify the solutions of a seal against the pre-hash of its block, and that they release the
+/// private key of its public key. Degenerate collisions, e.g. `(a + 2n, b)` and `(a, b)`, pass the
+/// pollard rho equations without revealing the key, but the next block must carry the key.
+fn verify_solutions(seal: &Seal, pre_hash: &H256, difficulty: Difficulty) -> bool {
+	let header = Compute { difficulty, pre_hash: *pre_hash, nonce: seal.nonce };
+	let pubkey = PublicKey::from_raw(seal.pubkey.clone());
+	let verifier = SolutionVerifier { pubkey };
+	let solutions = (
+		Solution::<Integer>::from_u256(&seal.solutions.0),
+		Solution::<Integer>::from_u256(&seal.solutions.1),
+	);
+	if !verifier.verify(&solutions, &header) {
+		return false
+	}
+	if seal.released_key().is_none() {
+		warn!("The solutions do not release the private key, cannot pass the block verification!");
+		return false
+	}
+	true
+}
+
 /// A minimal PoW algorithm that uses pollard rho method.
 /// Difficulty is fixed at 48 bit long uint.
-#[derive(Clone)]
-pub struct MinTREXAlgo;
+pub struct MinTREXAlgo<C> {
+	client: Arc<C>,
+	activation: u32,
+	keychains: Arc<KeychainCache<H256>>,
+}
+
+impl<C> MinTREXAlgo<C> {
+	/// Create the algorithm checking the keychain of the blocks from `activation` on.
+	pub fn new(client: Arc<C>, activation: u32) -> Self {
+		Self { client, activation, keychains: Default::default() }
+	}
+}
+
+impl<C> Clone for MinTREXAlgo<C> {
+	fn clone(&self) -> Self {
+		Self {
+			client: self.client.clone(),
+			activation: self.activation,
+			keychains: self.keychains.clone(),
+		}
+	}
+}
 
 // Here we implement the minimal TREX Pow Algorithm trait
-impl<B: BlockT<Hash = H256>> PowAlgorithm<B> for MinTREXAlgo {
+impl<B: BlockT<Hash = H256>, C> PowAlgorithm<B> for MinTREXAlgo<C>
+where
+	C: HeaderBackend<B>,
+{
 	type Difficulty = Difficulty;
 
 	fn difficulty(&self, _parent: B::Hash) -> Result<Self::Difficulty, Error<B>> {
@@ -277,7 +435,7 @@ impl<B: BlockT<Hash = H256>> PowAlgorithm<B> for MinTREXAlgo {
 
 	fn verify(
 		&self,
-		_parent: &BlockId<B>,
+		parent: &BlockId<B>,
 		pre_hash: &H256,
 		_pre_digest: Option<&[u8]>,
 		seal: &RawSeal,
@@ -289,20 +447,14 @@ impl<B: BlockT<Hash = H256>> PowAlgorithm<B> for MinTREXAlgo {
 			Err(_) => return Ok(false),
 		};
 
-		// Make sure the provided work actually comes from the correct pre_hash
-		let header = Compute { difficulty, pre_hash: *pre_hash, nonce: seal.nonce };
-		let raw_key = seal.pubkey;
-		let pubkey = PublicKey::from_raw(raw_key);
-		let verifier = SolutionVerifier { pubkey };
-		let solutions = (
-			Solution::<Integer>::from_u256(&seal.solutions.0),
-			Solution::<Integer>::from_u256(&seal.solutions.1),
-		);
-		if verifier.verify(&solutions, &header) {
-			return Ok(true)
+		// Make sure the public key is the one committed by the keychain of the parent block.
+		let client = self.client.as_ref();
+		if !verify_keychain(client, &self.keychains, self.activation, parent, &seal)? {
+			return Ok(false)
 		}
 
-		Ok(false)
+		// Make sure the provided work actually comes from the correct pre_hash
+		Ok(verify_solutions(&seal, pre_hash, difficulty))
 	}
 }
 
@@ -310,19 +462,27 @@ impl<B: BlockT<Hash = H256>> PowAlgorithm<B> for MinTREXAlgo {
 /// Needs a reference to the client so it can grab the difficulty from the runtime.
 pub struct TREXAlgo<C> {
 	client: Arc<C>,
+	activation: u32,
+	keychains: Arc<KeychainCache<H256>>,
 }
 
 impl<C> TREXAlgo<C> {
-	pub fn new(client: Arc<C>) -> Self {
-		Self { client }
+	/// Create the algorithm checking the keychain of the blocks from `activation` on.
+	pub fn new(client: Arc<C>, activation: u32) -> Self {
+		Self { client, activation, keychains: Default::default() }
 	}
 }
 
 // Manually implement clone. Deriving doesn't work because
 // it'll derive impl<C: Clone> Clone for TREXAlgorithm<C>. But C in practice isn't Clone.
+// The clones share the keychain cache.
 impl<C> Clone for TREXAlgo<C> {
 	fn clone(&self) -> Self {
-		Self::new(self.client.clone())
+		Self {
+			client: self.client.clone(),
+			activation: self.activation,
+			keychains: self.keychains.clone(),
+		}
 	}
 }
 
@@ -348,7 +508,7 @@ where
 
 	fn verify(
 		&self,
-		_parent: &BlockId<B>,
+		parent: &BlockId<B>,
 		pre_hash: &H256,
 		_pre_digest: Option<&[u8]>,
 		seal: &RawSeal,
@@ -373,16 +533,15 @@ where
 			return Ok(false)
 		}
 
-		// Make sure the provided work actually comes from the correct pre_hash
-		let header = Compute { difficulty, pre_hash: *pre_hash, nonce: seal.nonce };
-		let raw_key = seal.pubkey;
-		let pubkey = PublicKey::from_raw(raw_key);
-		let verifier = SolutionVerifier { pubkey };
-		let solutions = (
-			Solution::<Integer>::from_u256(&seal.solutions.0),
-			Solution::<Integer>::from_u256(&seal.solutions.1),
-		);
-		if verifier.verify(&solutions, &header) {
+		// Make sure the public key is the one committed by the keychain of the parent block.
+		let client = self.client.as_ref();
+		if !verify_keychain(client, &self.keychains, self.activation, parent, &seal)? {
+			return Ok(false)
+		}
+
+		// Make sure the provided work actually comes from the correct pre_hash, and it releases the
+		// private key.
+		if verify_solutions(&seal, pre_hash, difficulty) {
 			return Ok(true)
 		}
 		warn!("The block header cannot be verified!");
@@ -393,6 +552,8 @@ where
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::keychain::RawKeySeedsData;
+	use trex_constants::MAX_DIFFICULTY;
 	use elgamal_trex::KeyGenerator;
 	use rug::Integer;
 	use std::{sync::atomic::AtomicBool, thread};
@@ -455,6 +616,41 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn reject_solutions_without_released_key() {
+		let difficulty = 39;
+		let pubkey = get_test_pubkey(difficulty);
+		let mut compute = get_test_header(difficulty);
+		let solutions = (1..=10)
+			.find_map(|seed| pubkey.solve(&mut compute, Integer::from(seed)))
+			.expect("Cannot find the solutions!");
+		let mut seal = genesis::genesis_seal(difficulty as Difficulty);
+		seal.pubkey = pubkey.to_raw();
+		seal.nonce = compute.nonce;
+		seal.solutions = (solutions.0.to_u256(), solutions.1.to_u256());
+		assert!(verify_solutions(&seal, &compute.pre_hash, compute.difficulty));
+		assert!(seal.released_key().is_some());
+
+		// `g^(a + 2n) = g^a mod p`, so pairing a solution with itself shifted by `2n` passes the
+		// equations, but the key can not be solved from it.
+		let verifier = SolutionVerifier { pubkey: pubkey.clone() };
+		let mut passed = false;
+		for solution in [&solutions.0, &solutions.1] {
+			let mut shifted = solution.clone();
+			shifted.a += Integer::from(&shifted.n * 2);
+			let degenerate = (solution.clone(), shifted);
+			if !verifier.verify(&degenerate, &compute) {
+				continue
+			}
+			passed = true;
+			assert!(verifier.key_gen(&degenerate).is_none());
+			seal.solutions = (degenerate.0.to_u256(), degenerate.1.to_u256());
+			assert!(seal.released_key().is_none());
+			assert!(!verify_solutions(&seal, &compute.pre_hash, compute.difficulty));
+		}
+		assert!(passed, "A distinguished solution passes the equations when shifted!");
+	}
+
 	#[test]
 	fn try_pollard_rho_distributed() {
 		let mut threads = Vec::new();
@@ -523,6 +719,82 @@ mod tests {
 		// println!("{:?}", pubkey);
 	}
 
+	/// Build a child seal following the keychain of a parent seal without mining.
+	fn get_child_seal(parent: &Seal, difficulty: Difficulty) -> Seal {
+		let keychain = parent.keychain();
+		let mut seal = genesis::genesis_seal(difficulty);
+		seal.pubkey = keychain[(difficulty - MIN_DIFFICULTY) as usize].to_raw();
+		seal.seeds = yield_seeds(&keychain);
+		seal
+	}
+
+	#[test]
+	fn verify_child_pubkey() {
+		let parent = genesis::genesis_seal(INIT_DIFFICULTY);
+		let child = get_child_seal(&parent, INIT_DIFFICULTY);
+		assert_eq!(parent.verify_child(&child), Ok(()));
+		assert_eq!(verify_child_of(&parent.keychain(), &child), Ok(()));
+
+		// a public key of the same size but not committed by the parent is rejected.
+		let mut forged = child.clone();
+		forged.pubkey = get_test_pubkey(INIT_DIFFICULTY as u32).to_raw();
+		assert_eq!(parent.verify_child(&forged), Err(KeychainError::PubkeyMismatch));
+
+		// a public key from another slot of the keychain is rejected.
+		let mut shifted = child;
+		shifted.pubkey = parent.keychain()[0].to_raw();
+		assert_eq!(parent.verify_child(&shifted), Err(KeychainError::PubkeyMismatch));
+
+		let mut too_easy = get_child_seal(&parent, MIN_DIFFICULTY);
+		too_easy.difficulty = MIN_DIFFICULTY - 1;
+		assert_eq!(
+			parent.verify_child(&too_easy),
+			Err(KeychainError::DifficultyOutOfRange(MIN_DIFFICULTY - 1))
+		);
+	}
+
+	#[test]
+	fn verify_child_seeds() {
+		let parent = genesis::genesis_seal(INIT_DIFFICULTY);
+		let child = get_child_seal(&parent, INIT_DIFFICULTY);
+		assert_eq!(parent.verify_child(&child), Ok(()));
+
+		// tamper a single small seed.
+		let mut tampered = child.clone();
+		tampered.seeds[3] = RawKeySeedsData::U128(42u128);
+		assert_eq!(parent.verify_child(&tampered), Err(KeychainError::SeedMismatch(3)));
+
+		// tamper a single large seed.
+		let last = tampered.seeds.len() - 1;
+		let mut tampered = child.clone();
+		tampered.seeds[last] = RawKeySeedsData::U256(U256::from(42i32));
+		assert_eq!(parent.verify_child(&tampered), Err(KeychainError::SeedMismatch(last)));
+
+		// replacing a seed with the same value in another encoding is also rejected.
+		let idx = (128 - MIN_DIFFICULTY) as usize;
+		let mut tampered = child.clone();
+		if let RawKeySeedsData::U256(value) = child.seeds[idx] {
+			tampered.seeds[idx] = RawKeySeedsData::U128(value.low_u128());
+		}
+		assert_eq!(parent.verify_child(&tampered), Err(KeychainError::SeedMismatch(idx)));
+
+		// a grandchild following the child keychain is valid.
+		let grandchild = get_child_seal(&child, INIT_DIFFICULTY + 1);
+		assert_eq!(child.verify_child(&grandchild), Ok(()));
+	}
+
+	#[test]
+	fn keychain_depends_on_entropy() {
+		let parent = genesis::genesis_seal(INIT_DIFFICULTY);
+		let mut resealed = parent.clone();
+		resealed.nonce = U256::from(2i32);
+		assert_ne!(parent.entropy(), resealed.entropy());
+		// the same seeds sealed by different solutions lead to different keychains.
+		assert_ne!(parent.keychain()[0].p, resealed.keychain()[0].p);
+		let child = get_child_seal(&parent, INIT_DIFFICULTY);
+		assert_eq!(resealed.verify_child(&child), Err(KeychainError::PubkeyMismatch));
+	}
+
 	#[test]
 	fn test_seeds_len() {
 		let mut genesis_key_seeds: RawKeySeeds =
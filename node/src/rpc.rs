@@ -8,7 +8,9 @@
 use std::sync::Arc;
 
 use jsonrpsee::RpcModule;
-use trex_runtime::{opaque::Block, AccountId, Balance, Index};
+use sc_client_api::{AuxStore, BlockchainEvents};
+use sc_rpc::SubscriptionTaskExecutor;
+use trex_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Index};
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
@@ -16,6 +18,8 @@ use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 
 pub use sc_rpc_api::DenyUnsafe;
 
+pub mod trex;
+
 /// Full client dependencies.
 pub struct FullDeps<C, P> {
 	/// The client instance to use.
@@ -24,6 +28,8 @@ pub struct FullDeps<C, P> {
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Executor to drive the subscriptions.
+	pub subscription_executor: SubscriptionTaskExecutor,
 }
 
 /// Instantiate all full RPC extensions.
@@ -33,11 +39,13 @@ pub fn create_full<C, P>(
 	where
 		C: ProvideRuntimeApi<Block>,
 		C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+		C: BlockchainEvents<Block> + AuxStore,
 		C: Send + Sync + 'static,
 		C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 		C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 		C::Api: pallet_storage_rpc::SumStorageRuntimeApi<Block>,
-		C::Api: pallet_difficulty_rpc::DiffAdjustmentRuntimeApi<Block>,
+		C::Api: pallet_difficulty_rpc::DiffAdjustmentRuntimeApi<Block, BlockNumber>,
+		C::Api: pallet_trex_rpc::CiphertextRuntimeApi<Block, AccountId, Balance, BlockNumber>,
 		C::Api: BlockBuilder<Block>,
 		P: TransactionPool + 'static,
 {
@@ -45,13 +53,17 @@ pub fn create_full<C, P>(
 	use substrate_frame_rpc_system::{System, SystemApiServer};
 	use pallet_storage_rpc::{SumStorage, SumStorageApiServer};
 	use pallet_difficulty_rpc::{DiffAdjustment, DiffAdjustmentApiServer};
+	use pallet_trex_rpc::{CiphertextApiServer, Ciphertexts};
+	use trex::{Trex, TrexApiServer};
 
 	let mut module = RpcModule::new(());
-	let FullDeps { client, pool, deny_unsafe } = deps;
+	let FullDeps { client, pool, deny_unsafe, subscription_executor } = deps;
 
 	module.merge(System::new(client.clone(), pool.clone(), deny_unsafe).into_rpc())?;
 	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
 	module.merge(DiffAdjustment::new(client.clone()).into_rpc())?;
+	module.merge(Ciphertexts::new(client.clone()).into_rpc())?;
+	module.merge(Trex::new(client.clone(), subscription_executor).into_rpc())?;
 	module.merge(SumStorage::new(client).into_rpc())?;
 
 	// Extend this RPC with a custom API by using the following syntax.
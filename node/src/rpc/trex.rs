@@ -0,0 +1,316 @@
+//! RPC interface for the private keys released by mining TREX blocks.
+
+use futures::{FutureExt, StreamExt};
+use jsonrpsee::{
+	core::{async_trait, Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorCode, ErrorObject},
+	ws_server::SubscriptionSink,
+};
+use sc_client_api::{AuxStore, BlockchainEvents};
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, One, Zero},
+};
+use elgamal_trex::elgamal::PublicKey;
+use std::{marker::PhantomData, sync::Arc};
+use trex_constants::{Difficulty, MIN_DIFFICULTY};
+use trex_pow::{
+	archive::{self, ReleasedKey},
+	keychain::KeychainCache,
+	seal_from_header,
+};
+
+/// Maximum number of keys returned by a single range query.
+const MAX_RELEASED_KEYS: u32 = 1000;
+
+/// Reference to a block either by its number or its hash.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum NumberOrHash<Number, Hash> {
+	/// The block number on the best chain.
+	Number(Number),
+	/// The block hash.
+	Hash(Hash),
+}
+
+/// A private key released by mining a block, big integers are encoded as decimal strings.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleasedKeyInfo<Number, Hash> {
+	/// Hash of the block releasing the key.
+	pub block_hash: Hash,
+	/// Number of the block releasing the key.
+	pub block_number: Number,
+	/// Bit length of the key.
+	pub bit_length: u32,
+	/// The prime modulus.
+	pub p: String,
+	/// The generator.
+	pub g: String,
+	/// The public key h = g^x mod p.
+	pub h: String,
+	/// The released private key.
+	pub x: String,
+}
+
+impl<Number, Hash> ReleasedKeyInfo<Number, Hash> {
+	/// Derive the released key from the seal in a block header.
+	pub fn from_header<H: HeaderT<Number = Number, Hash = Hash>>(header: &H) -> Option<Self> {
+		let seal = seal_from_header(header)?;
+		let key = seal.released_key()?;
+		Some(Self {
+			block_hash: header.hash(),
+			block_number: *header.number(),
+			bit_length: key.bit_length,
+			p: key.p.to_string(),
+			g: key.g.to_string(),
+			h: seal.pubkey.h.to_string(),
+			x: key.x.to_string(),
+		})
+	}
+}
+
+impl<Number, Hash> From<ReleasedKey<Hash, Number>> for ReleasedKeyInfo<Number, Hash> {
+	fn from(key: ReleasedKey<Hash, Number>) -> Self {
+		let pubkey = key.public_key();
+		Self {
+			block_hash: key.hash,
+			block_number: key.number,
+			bit_length: key.bit_length,
+			p: pubkey.p.to_string(),
+			g: pubkey.g.to_string(),
+			h: pubkey.h.to_string(),
+			x: key.private_key().x.to_string(),
+		}
+	}
+}
+
+/// A public key in the keychain, big integers are encoded as decimal strings.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyInfo {
+	/// Bit length of the key.
+	pub bit_length: u32,
+	/// The prime modulus.
+	pub p: String,
+	/// The generator.
+	pub g: String,
+	/// The public key h = g^x mod p.
+	pub h: String,
+}
+
+impl From<&PublicKey> for PublicKeyInfo {
+	fn from(key: &PublicKey) -> Self {
+		Self {
+			bit_length: key.bit_length,
+			p: key.p.to_string(),
+			g: key.g.to_string(),
+			h: key.h.to_string(),
+		}
+	}
+}
+
+/// The public key to be mined in the block next to a sealed block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpcomingPublicKey<Number> {
+	/// Number of the block which will release the private key.
+	pub block_number: Number,
+	/// The public key at the requested difficulty.
+	pub pubkey: PublicKeyInfo,
+	/// The full keychain of all difficulties, only returned when asked.
+	pub keychain: Option<Vec<PublicKeyInfo>>,
+}
+
+/// TREX RPC methods.
+#[rpc(server, client)]
+pub trait TrexApi<BlockNumber, BlockHash> {
+	/// Get the private key released by a block.
+	#[method(name = "trex_getReleasedKey")]
+	fn get_released_key(
+		&self,
+		block: NumberOrHash<BlockNumber, BlockHash>,
+	) -> RpcResult<Option<ReleasedKeyInfo<BlockNumber, BlockHash>>>;
+
+	/// Get the private keys released by the best chain within a range of blocks (inclusive).
+	#[method(name = "trex_getReleasedKeys")]
+	fn get_released_keys(
+		&self,
+		from: BlockNumber,
+		to: BlockNumber,
+	) -> RpcResult<Vec<ReleasedKeyInfo<BlockNumber, BlockHash>>>;
+
+	/// Get the public key that will be mined at a difficulty in the block next to `at`, which is
+	/// the best block if not supplied. The full keychain is returned when `full_keychain` is set.
+	#[method(name = "trex_getUpcomingPublicKey")]
+	fn get_upcoming_public_key(
+		&self,
+		difficulty: Difficulty,
+		at: Option<BlockHash>,
+		full_keychain: Option<bool>,
+	) -> RpcResult<UpcomingPublicKey<BlockNumber>>;
+
+	/// Subscribe the private keys released by new best blocks.
+	#[subscription(
+		name = "trex_subscribeReleasedKeys" => "trex_releasedKey",
+		unsubscribe = "trex_unsubscribeReleasedKeys",
+		item = ReleasedKeyInfo<BlockNumber, BlockHash>
+	)]
+	fn subscribe_released_keys(&self);
+}
+
+/// Implementation of the TREX RPC methods.
+pub struct Trex<C, B: BlockT> {
+	client: Arc<C>,
+	executor: SubscriptionTaskExecutor,
+	keychains: KeychainCache<B::Hash>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B: BlockT> Trex<C, B> {
+	/// Create new `Trex` instance with the given reference to the client.
+	pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+		Self { client, executor, keychains: Default::default(), _marker: Default::default() }
+	}
+}
+
+fn rpc_error(message: String) -> JsonRpseeError {
+	JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+		ErrorCode::InvalidParams.code(),
+		message,
+		None::<()>,
+	)))
+}
+
+fn archive_error(e: sp_blockchain::Error) -> JsonRpseeError {
+	rpc_error(format!("Failed to read the key archive: {:?}", e))
+}
+
+impl<C, B> Trex<C, B>
+where
+	B: BlockT,
+	C: HeaderBackend<B> + AuxStore,
+{
+	/// Get the key released by a block from the key archive, the key is derived from the block
+	/// header if it is not archived.
+	fn released_key_at(
+		&self,
+		id: BlockId<B>,
+	) -> RpcResult<Option<ReleasedKeyInfo<NumberFor<B>, B::Hash>>> {
+		let client = self.client.as_ref();
+		let archived = match id {
+			BlockId::Hash(hash) => archive::load_released_key::<B, C>(client, &hash),
+			BlockId::Number(number) => archive::load_canonical_key::<B, C>(client, number),
+		}
+		.map_err(archive_error)?;
+		if let Some(key) = archived {
+			return Ok(Some(key.into()))
+		}
+		let header = self
+			.client
+			.header(id)
+			.map_err(|e| rpc_error(format!("Failed to fetch block header: {:?}", e)))?;
+		Ok(header.and_then(|header| {
+			// the genesis block does not release any key.
+			if header.number().is_zero() {
+				return None
+			}
+			ReleasedKeyInfo::from_header(&header)
+		}))
+	}
+}
+
+#[async_trait]
+impl<C, B> TrexApiServer<NumberFor<B>, <B as BlockT>::Hash> for Trex<C, B>
+where
+	B: BlockT + 'static,
+	C: HeaderBackend<B> + AuxStore + BlockchainEvents<B> + Send + Sync + 'static,
+{
+	fn get_released_key(
+		&self,
+		block: NumberOrHash<NumberFor<B>, B::Hash>,
+	) -> RpcResult<Option<ReleasedKeyInfo<NumberFor<B>, B::Hash>>> {
+		let id = match block {
+			NumberOrHash::Number(number) => BlockId::Number(number),
+			NumberOrHash::Hash(hash) => BlockId::Hash(hash),
+		};
+		self.released_key_at(id)
+	}
+
+	fn get_released_keys(
+		&self,
+		from: NumberFor<B>,
+		to: NumberFor<B>,
+	) -> RpcResult<Vec<ReleasedKeyInfo<NumberFor<B>, B::Hash>>> {
+		if from > to {
+			return Err(rpc_error(format!("Invalid block range from {:?} to {:?}", from, to)))
+		}
+		if to - from >= MAX_RELEASED_KEYS.into() {
+			return Err(rpc_error(format!(
+				"Block range is too large, at most {} keys can be fetched",
+				MAX_RELEASED_KEYS
+			)))
+		}
+		let mut keys = Vec::new();
+		let mut number = from;
+		while number <= to {
+			if let Some(key) = self.released_key_at(BlockId::Number(number))? {
+				keys.push(key);
+			}
+			number += One::one();
+		}
+		Ok(keys)
+	}
+
+	fn get_upcoming_public_key(
+		&self,
+		difficulty: Difficulty,
+		at: Option<B::Hash>,
+		full_keychain: Option<bool>,
+	) -> RpcResult<UpcomingPublicKey<NumberFor<B>>> {
+		let full_keychain = full_keychain.unwrap_or(false);
+		let at = at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash);
+		let header = self
+			.client
+			.header(BlockId::Hash(at))
+			.map_err(|e| rpc_error(format!("Failed to fetch block header: {:?}", e)))?
+			.ok_or_else(|| rpc_error(format!("Block {:?} is not found", at)))?;
+		let seal = seal_from_header(&header)
+			.ok_or_else(|| rpc_error(format!("Seal of block {:?} is not found", at)))?;
+		let keychain = self.keychains.get_or_derive(&at, || seal.keychain());
+		let pubkey = difficulty
+			.checked_sub(MIN_DIFFICULTY)
+			.and_then(|index| keychain.get(index as usize))
+			.ok_or_else(|| rpc_error(format!("Difficulty {} is out of keychain range", difficulty)))?;
+		Ok(UpcomingPublicKey {
+			block_number: *header.number() + One::one(),
+			pubkey: pubkey.into(),
+			keychain: if full_keychain {
+				Some(keychain.iter().map(Into::into).collect())
+			} else {
+				None
+			},
+		})
+	}
+
+	fn subscribe_released_keys(&self, mut sink: SubscriptionSink) -> RpcResult<()> {
+		let stream = self
+			.client
+			.import_notification_stream()
+			.filter(|notification| futures::future::ready(notification.is_new_best))
+			.filter_map(|notification| {
+				futures::future::ready(ReleasedKeyInfo::from_header(&notification.header))
+			});
+		let fut = async move {
+			sink.pipe_from_stream(stream).await;
+		};
+		self.executor.spawn("trex-rpc-subscription", Some("rpc"), fut.boxed());
+		Ok(())
+	}
+}
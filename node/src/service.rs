@@ -14,7 +14,9 @@ use sp_core::{
 };
 use sp_runtime::{generic::BlockId, traits::Block as BlockT};
 use std::{sync::Arc, thread, time::Duration};
-use trex_constants::{INIT_DIFFICULTY, MINING_WORKER_BUILD_TIME, MINING_WORKER_TIMEOUT};
+use trex_constants::{
+	INIT_DIFFICULTY, KEYCHAIN_DIGEST_HEIGHT, MINING_WORKER_BUILD_TIME, MINING_WORKER_TIMEOUT,
+};
 #[cfg(feature = "min-algo")]
 use trex_pow::MinTREXAlgo;
 #[cfg(not(feature = "min-algo"))]
@@ -31,7 +33,11 @@ use std::{
 	str::FromStr,
 	sync::atomic::{AtomicBool, Ordering},
 };
-use trex_pow::distributed::DistBlockImport;
+use trex_pow::{
+	archive::KeyArchiveBlockImport,
+	digest::{KeychainBlockImport, KeychainProposerFactory},
+	distributed::DistBlockImport,
+};
 
 // Our native executor instance.
 pub struct ExecutorDispatch;
@@ -100,29 +106,52 @@ pub(crate) type FullClient =
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
-pub struct CreateInherentDataProviders;
+/// Create the inherent data providers of a block, both to author it and to check it on import.
+pub struct CreateInherentDataProviders {
+	client: Arc<FullClient>,
+}
+
+impl CreateInherentDataProviders {
+	pub fn new(client: Arc<FullClient>) -> Self {
+		Self { client }
+	}
+}
 
 #[async_trait]
 impl sp_inherents::CreateInherentDataProviders<Block, ()> for CreateInherentDataProviders {
-	type InherentDataProviders = sp_timestamp::InherentDataProvider;
+	type InherentDataProviders =
+		(sp_timestamp::InherentDataProvider, trex_inherent::InherentDataProvider);
 
 	async fn create_inherent_data_providers(
 		&self,
-		_parent: <Block as BlockT>::Hash,
+		parent: <Block as BlockT>::Hash,
 		_extra_args: (),
 	) -> Result<Self::InherentDataProviders, Box<dyn std::error::Error + Send + Sync>> {
-		Ok(sp_timestamp::InherentDataProvider::from_system_time())
+		let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+		// the key released by the seal of the parent, which is checked against its public key.
+		let seal = self
+			.client
+			.header(BlockId::Hash(parent))?
+			.as_ref()
+			.and_then(trex_pow::seal_from_header)
+			.map(|seal| seal.encode());
+		let trex_data = trex_inherent::InherentDataProvider::from_seal(seal.as_deref());
+		Ok((timestamp, trex_data))
 	}
 }
 
 #[cfg(feature = "min-algo")]
-type PowAlgo = MinTREXAlgo;
+type PowAlgo = MinTREXAlgo<FullClient>;
 #[cfg(not(feature = "min-algo"))]
 type PowAlgo = TREXAlgo<FullClient>;
 
 type PowBlockImport = sc_consensus_pow::PowBlockImport<
 	Block,
-	DistBlockImport<Block, Arc<FullClient>, FullClient>,
+	KeyArchiveBlockImport<
+		Block,
+		KeychainBlockImport<Block, DistBlockImport<Block, Arc<FullClient>, FullClient>>,
+		FullClient,
+	>,
 	FullClient,
 	FullSelectChain,
 	PowAlgo,
@@ -196,9 +225,9 @@ pub fn new_partial(
 	let can_author_with = sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
 
 	#[cfg(feature = "min-algo")]
-	let algorithm = trex_pow::MinTREXAlgo;
+	let algorithm = trex_pow::MinTREXAlgo::new(client.clone(), KEYCHAIN_DIGEST_HEIGHT);
 	#[cfg(not(feature = "min-algo"))]
-	let algorithm = trex_pow::TREXAlgo::new(client.clone());
+	let algorithm = trex_pow::TREXAlgo::new(client.clone(), KEYCHAIN_DIGEST_HEIGHT);
 
 	// Initialize an AtomicBool Arc pointer.
 	let found = Arc::new(AtomicBool::new(false));
@@ -207,14 +236,20 @@ pub fn new_partial(
 	let dist_block_import =
 		distributed::DistBlockImport::new(client.clone(), client.clone(), found.clone());
 
+	// Reject the blocks without a seal or whose keychain digest does not match their seal.
+	let keychain_block_import = KeychainBlockImport::new(dist_block_import, KEYCHAIN_DIGEST_HEIGHT);
+
+	// Archive the private key released by every imported block.
+	let archive_block_import = KeyArchiveBlockImport::new(keychain_block_import, client.clone());
+
 	// Replace the middleware dist_block_import with the previous client Arc pointer.
 	let pow_block_import = sc_consensus_pow::PowBlockImport::new(
-		dist_block_import,
+		archive_block_import,
 		Arc::clone(&client),
 		algorithm.clone(),
 		0, // check inherent starting at block 0
 		select_chain.clone(),
-		CreateInherentDataProviders,
+		CreateInherentDataProviders::new(Arc::clone(&client)),
 		can_author_with,
 	);
 
@@ -290,6 +325,8 @@ pub fn new_full(
 			prometheus_registry.as_ref(),
 			telemetry.as_ref().map(|x| x.handle()),
 		);
+		// Put the keychain digest committed by the parent seal in every proposed block.
+		let proposer = KeychainProposerFactory::new(proposer, algorithm.clone());
 
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
@@ -310,11 +347,7 @@ pub fn new_full(
 				// Here, the pre-runtime item is the public key for time release encryption.
 				Some(author.encode()),
 				// For block production we want to provide our inherent data provider
-				|_parent, ()| async {
-					let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
-					// let trex_data = trex_inherent::InherentDataProvider::from_default_value();
-					Ok(timestamp)
-				},
+				CreateInherentDataProviders::new(Arc::clone(&client)),
 				// time to wait for a new block before starting to mine a new one
 				Duration::from_secs(MINING_WORKER_TIMEOUT),
 				// how long to take to actually build the block (i.e. executing extrinsics)
@@ -414,9 +447,13 @@ pub fn new_full(
 		let client = client.clone();
 		let pool = transaction_pool.clone();
 
-		Box::new(move |deny_unsafe, _| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: pool.clone(), deny_unsafe };
+		Box::new(move |deny_unsafe, subscription_executor| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+				subscription_executor,
+			};
 			crate::rpc::create_full(deps).map_err(Into::into)
 		})
 	};
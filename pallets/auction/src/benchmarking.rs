@@ -0,0 +1,101 @@
+//! Benchmarking setup for pallet-trex-auction
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Auction;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+use sp_runtime::traits::Bounded;
+
+/// The close block of the benchmarked auctions.
+const CLOSE: u32 = 10;
+
+/// A key over a 48 bit safe prime, the cost of the arithmetic is dominated by the big integers.
+fn bench_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(98765432109u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+/// A sealed bid of an amount to the key of the close block.
+fn sealed(amount: u32) -> KeyCapsule {
+	elgamal::encrypt(&bench_key(), CLOSE, amount.into(), U256::from(987654u32 + amount))
+}
+
+fn funded<T: Config>(who: &T::AccountId) {
+	T::Currency::make_free_balance_be(who, BalanceOf::<T>::max_value() / 2u32.into());
+}
+
+/// Create an auction closed at `CLOSE` with `n` sealed bids, returns the amounts of the bidders.
+fn auction_with_bids<T: Config>(n: u32) -> Vec<(T::AccountId, U256)> {
+	let owner: T::AccountId = account("owner", 0, 0);
+	funded::<T>(&owner);
+	frame_system::Pallet::<T>::set_block_number(1u32.into());
+	let close: T::BlockNumber = CLOSE.into();
+	Auction::<T>::create_auction(RawOrigin::Signed(owner).into(), close, T::MinBidBond::get())
+		.expect("the owner is funded");
+	(0..n)
+		.map(|i| {
+			let bidder: T::AccountId = account("bidder", i, 0);
+			funded::<T>(&bidder);
+			Auction::<T>::place_bid(RawOrigin::Signed(bidder.clone()).into(), 0, sealed(i + 1))
+				.expect("the bidder is funded");
+			(bidder, U256::from(i + 1))
+		})
+		.collect()
+}
+
+/// Mine the block after the close block, which stores the key released by the close block.
+fn mine_after_close<T: Config>() {
+	frame_system::Pallet::<T>::set_block_number((CLOSE + 1).into());
+	T::ReleasedKeys::insert_released_key(CLOSE.into(), bench_key());
+}
+
+benchmarks! {
+	create_auction {
+		let caller: T::AccountId = whitelisted_caller();
+		funded::<T>(&caller);
+		let close: T::BlockNumber = CLOSE.into();
+	}: _(RawOrigin::Signed(caller), close, T::MinBidBond::get())
+	verify {
+		assert!(Auctions::<T>::contains_key(0));
+	}
+
+	place_bid {
+		auction_with_bids::<T>(0);
+		let caller: T::AccountId = whitelisted_caller();
+		funded::<T>(&caller);
+	}: _(RawOrigin::Signed(caller.clone()), 0, sealed(42))
+	verify {
+		assert!(Bids::<T>::contains_key(0, &caller));
+	}
+
+	reveal_bids {
+		let n in 1 .. T::MaxBids::get();
+		let amounts = auction_with_bids::<T>(n);
+		mine_after_close::<T>();
+		let caller: T::AccountId = whitelisted_caller();
+	}: _(RawOrigin::Signed(caller), 0, amounts)
+	verify {
+		assert!(Bids::<T>::iter_prefix_values(0).all(|bid| bid.amount.is_some()));
+	}
+
+	// The worst case pays the winner and releases the bonds of every other bid.
+	settle {
+		let b in 1 .. T::MaxBids::get();
+		let amounts = auction_with_bids::<T>(b);
+		mine_after_close::<T>();
+		let caller: T::AccountId = whitelisted_caller();
+		Auction::<T>::reveal_bids(RawOrigin::Signed(caller.clone()).into(), 0, amounts)
+			.expect("the amounts are the sealed bids");
+		let after_reveal = T::BlockNumber::from(CLOSE + 1).saturating_add(T::RevealPeriod::get());
+		frame_system::Pallet::<T>::set_block_number(after_reveal);
+	}: _(RawOrigin::Signed(caller), 0)
+	verify {
+		assert!(!Auctions::<T>::contains_key(0));
+	}
+
+	impl_benchmark_test_suite!(Auction, crate::mock::new_test_ext(), crate::mock::Test);
+}
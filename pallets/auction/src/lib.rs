@@ -0,0 +1,364 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Sealed-bid auctions built on the timed release encryption of TREX.
+//!
+//! A bid is an ElGamal key capsule of the bid amount encrypted to the public key mined at the
+//! close block of the auction, so nobody, including the auctioneer, can read the bids before the
+//! auction closes. Once the close block is mined, its private key is released by the seal and
+//! stored on chain by the next block, then anyone can reveal the bids, which are checked with the
+//! stored key. The highest revealed bid wins the auction at settlement.
+use frame_support::traits::{
+	BalanceStatus, Currency, ExistenceRequirement, Get, ReservableCurrency,
+};
+pub use pallet::*;
+pub use pallet_trex::{elgamal, KeyCapsule, RawReleasedKey, ReleasedKeys};
+use sp_core::U256;
+use sp_runtime::traits::Saturating;
+use sp_std::{cmp::Reverse, vec::Vec};
+pub mod weights;
+pub use weights::AuctionWeight;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	/// Identifier of an auction.
+	pub type AuctionId = u32;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency to reserve the deposits and bonds, and to pay for the won auctions.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// The deposit reserved from the auctioneer until the auction is settled.
+		#[pallet::constant]
+		type AuctionDeposit: Get<BalanceOf<Self>>;
+
+		/// The minimum bond an auctioneer can ask from the bidders.
+		#[pallet::constant]
+		type MinBidBond: Get<BalanceOf<Self>>;
+
+		/// Number of blocks after the close block to reveal the bids.
+		#[pallet::constant]
+		type RevealPeriod: Get<Self::BlockNumber>;
+
+		/// Maximum number of bids of an auction.
+		#[pallet::constant]
+		type MaxBids: Get<u32>;
+
+		/// The keys released by the mined blocks, which reveal the bids.
+		type ReleasedKeys: ReleasedKeys<Self::BlockNumber>;
+
+		/// Weight information for extrinsics in this pallet.
+		type AuctionWeight: AuctionWeight;
+	}
+
+	/// Type alias for currency balance.
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	/// An auction with its terms.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct AuctionInfo<AccountId, Balance, BlockNumber> {
+		/// Account which created the auction and receives the payment of the winner.
+		pub owner: AccountId,
+		/// Deposit reserved from the owner until the auction is settled.
+		pub deposit: Balance,
+		/// The block whose key encrypts the bids, no bid is accepted from this block.
+		pub close: BlockNumber,
+		/// The bond reserved from each bidder, which is forfeited if the winner can not pay.
+		pub bid_bond: Balance,
+		/// Number of the bids placed.
+		pub bids: u32,
+	}
+
+	pub type AuctionInfoOf<T> = AuctionInfo<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+	>;
+
+	/// A sealed bid of an auction.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct BidInfo<Balance> {
+		/// Order of the bid in the auction, which breaks ties of the amounts.
+		pub index: u32,
+		/// Bond reserved from the bidder.
+		pub bond: Balance,
+		/// The bid amount encrypted to the key of the close block.
+		pub capsule: KeyCapsule,
+		/// The bid amount once it is revealed.
+		pub amount: Option<U256>,
+	}
+
+	/// The identifier of the next auction.
+	#[pallet::storage]
+	#[pallet::getter(fn next_auction_id)]
+	pub type NextAuctionId<T> = StorageValue<_, AuctionId, ValueQuery>;
+
+	/// Auctions which are not settled yet.
+	#[pallet::storage]
+	#[pallet::getter(fn auctions)]
+	pub type Auctions<T: Config> = StorageMap<_, Twox64Concat, AuctionId, AuctionInfoOf<T>>;
+
+	/// The bids of each auction by their bidders.
+	#[pallet::storage]
+	#[pallet::getter(fn bids)]
+	pub type Bids<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		AuctionId,
+		Blake2_128Concat,
+		T::AccountId,
+		BidInfo<BalanceOf<T>>,
+	>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// An auction is created. \[auction_id, owner, close\]
+		AuctionCreated(AuctionId, T::AccountId, T::BlockNumber),
+		/// A sealed bid is placed. \[auction_id, bidder\]
+		BidPlaced(AuctionId, T::AccountId),
+		/// A bid is revealed with the released key. \[auction_id, bidder, amount\]
+		BidRevealed(AuctionId, T::AccountId, U256),
+		/// An auction is settled with its winner and the paid amount, if any.
+		/// \[auction_id, winner\]
+		AuctionSettled(AuctionId, Option<(T::AccountId, BalanceOf<T>)>),
+		/// The winner can not pay the bid, whose bond is paid to the owner instead.
+		/// \[auction_id, bidder, bond\]
+		WinnerDefaulted(AuctionId, T::AccountId, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The close block of the auction is not in the future.
+		CloseNotInFuture,
+		/// The bond is lower than `MinBidBond`.
+		BondTooLow,
+		/// The auctioneer can not afford the deposit.
+		InsufficientDeposit,
+		/// No auction is found with the identifier.
+		AuctionNotFound,
+		/// The auction does not accept bids after its close block.
+		BiddingClosed,
+		/// The bid is not encrypted to the key of the close block.
+		InvalidTarget,
+		/// The key capsule of the bid is malformed.
+		InvalidCapsule,
+		/// The bidder already placed a bid in the auction.
+		AlreadyBid,
+		/// The auction has reached `MaxBids`.
+		TooManyBids,
+		/// The bidder can not afford the bond.
+		InsufficientBond,
+		/// The key of the close block is not released yet.
+		KeyNotReleased,
+		/// The reveal period of the auction is over.
+		RevealPeriodOver,
+		/// The reveal period of the auction is not over yet.
+		RevealPeriodNotOver,
+		/// No bid of the bidder is found in the auction.
+		BidNotFound,
+		/// The bid is already revealed.
+		AlreadyRevealed,
+		/// The amount is not the decryption of the bid.
+		InvalidReveal,
+		/// Next auction id overflows.
+		AuctionIdOverflow,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Create an auction closed at a future block, bidders must reserve `bid_bond` with their
+		/// bids. The `AuctionDeposit` is reserved from the owner until the auction is settled.
+		#[pallet::weight(T::AuctionWeight::create_auction())]
+		pub fn create_auction(
+			origin: OriginFor<T>,
+			close: T::BlockNumber,
+			bid_bond: BalanceOf<T>,
+		) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			ensure!(
+				close > frame_system::Pallet::<T>::block_number(),
+				Error::<T>::CloseNotInFuture
+			);
+			ensure!(bid_bond >= T::MinBidBond::get(), Error::<T>::BondTooLow);
+
+			let id = NextAuctionId::<T>::get();
+			let next = id.checked_add(1).ok_or(Error::<T>::AuctionIdOverflow)?;
+			let deposit = T::AuctionDeposit::get();
+			T::Currency::reserve(&owner, deposit).map_err(|_| Error::<T>::InsufficientDeposit)?;
+
+			NextAuctionId::<T>::put(next);
+			let info = AuctionInfo { owner: owner.clone(), deposit, close, bid_bond, bids: 0 };
+			Auctions::<T>::insert(id, info);
+			Self::deposit_event(Event::AuctionCreated(id, owner, close));
+			Ok(())
+		}
+
+		/// Place a sealed bid, which is the bid amount encrypted to the key of the close block.
+		#[pallet::weight(T::AuctionWeight::place_bid())]
+		pub fn place_bid(
+			origin: OriginFor<T>,
+			auction_id: AuctionId,
+			capsule: KeyCapsule,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut auction = Auctions::<T>::get(auction_id).ok_or(Error::<T>::AuctionNotFound)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(now < auction.close, Error::<T>::BiddingClosed);
+			ensure!(
+				T::BlockNumber::from(capsule.target) == auction.close,
+				Error::<T>::InvalidTarget
+			);
+			ensure!(
+				capsule.has_valid_bit_length() && capsule.is_reduced(),
+				Error::<T>::InvalidCapsule
+			);
+			ensure!(!Bids::<T>::contains_key(auction_id, &who), Error::<T>::AlreadyBid);
+			ensure!(auction.bids < T::MaxBids::get(), Error::<T>::TooManyBids);
+			T::Currency::reserve(&who, auction.bid_bond)
+				.map_err(|_| Error::<T>::InsufficientBond)?;
+
+			let bid =
+				BidInfo { index: auction.bids, bond: auction.bid_bond, capsule, amount: None };
+			Bids::<T>::insert(auction_id, &who, bid);
+			auction.bids += 1;
+			Auctions::<T>::insert(auction_id, auction);
+			Self::deposit_event(Event::BidPlaced(auction_id, who));
+			Ok(())
+		}
+
+		/// Reveal the amounts of bids, which can be dispatched by anyone once the key of the close
+		/// block is stored on chain and until the reveal period is over.
+		///
+		/// Every amount is checked to be the decryption of its bid with the key stored for the
+		/// close block.
+		#[pallet::weight(T::AuctionWeight::reveal_bids(amounts.len() as u32))]
+		pub fn reveal_bids(
+			origin: OriginFor<T>,
+			auction_id: AuctionId,
+			amounts: Vec<(T::AccountId, U256)>,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			let auction = Auctions::<T>::get(auction_id).ok_or(Error::<T>::AuctionNotFound)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(
+				now <= auction.close.saturating_add(T::RevealPeriod::get()),
+				Error::<T>::RevealPeriodOver
+			);
+			ensure!(amounts.len() <= T::MaxBids::get() as usize, Error::<T>::TooManyBids);
+			let key =
+				T::ReleasedKeys::released_key(auction.close).ok_or(Error::<T>::KeyNotReleased)?;
+
+			// check all the reveals before writing any of them.
+			let mut revealed = Vec::with_capacity(amounts.len());
+			for (who, amount) in amounts {
+				let mut bid = Bids::<T>::get(auction_id, &who).ok_or(Error::<T>::BidNotFound)?;
+				ensure!(bid.amount.is_none(), Error::<T>::AlreadyRevealed);
+				ensure!(elgamal::verify(&key, &bid.capsule, amount), Error::<T>::InvalidReveal);
+				bid.amount = Some(amount);
+				revealed.push((who, bid));
+			}
+
+			for (who, bid) in revealed {
+				let amount = bid.amount.unwrap_or_default();
+				Bids::<T>::insert(auction_id, &who, bid);
+				Self::deposit_event(Event::BidRevealed(auction_id, who, amount));
+			}
+			Ok(())
+		}
+
+		/// Settle an auction after its reveal period, which can be dispatched by anyone.
+		///
+		/// The highest revealed bid wins and the earlier bid wins a tie. The winner pays the
+		/// amount to the owner, or forfeits the bond to the owner if it can not pay. The bonds of
+		/// the other bids and the deposit of the owner are released.
+		#[pallet::weight(T::AuctionWeight::settle(T::MaxBids::get()))]
+		pub fn settle(origin: OriginFor<T>, auction_id: AuctionId) -> DispatchResult {
+			ensure_signed(origin)?;
+			let auction = Auctions::<T>::get(auction_id).ok_or(Error::<T>::AuctionNotFound)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(
+				now > auction.close.saturating_add(T::RevealPeriod::get()),
+				Error::<T>::RevealPeriodNotOver
+			);
+
+			Auctions::<T>::remove(auction_id);
+			let bids: Vec<_> = Bids::<T>::drain_prefix(auction_id).collect();
+			let winner = bids
+				.iter()
+				.filter_map(|(who, bid)| bid.amount.map(|amount| (amount, Reverse(bid.index), who)))
+				.max()
+				.map(|(amount, _, who)| (who.clone(), amount));
+
+			for (who, bid) in bids.iter() {
+				if winner.as_ref().map_or(true, |(winner, _)| winner != who) {
+					T::Currency::unreserve(who, bid.bond);
+				}
+			}
+			T::Currency::unreserve(&auction.owner, auction.deposit);
+
+			let sale = winner.and_then(|(who, amount)| {
+				Self::pay(auction_id, &auction.owner, &who, auction.bid_bond, amount)
+					.map(|paid| (who, paid))
+			});
+			Self::deposit_event(Event::AuctionSettled(auction_id, sale));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Transfer the winning amount to the owner and release the bond of the winner, returns the
+	/// paid amount. The bond is paid to the owner instead if the winner can not afford it.
+	fn pay(
+		auction_id: AuctionId,
+		owner: &T::AccountId,
+		winner: &T::AccountId,
+		bond: BalanceOf<T>,
+		amount: U256,
+	) -> Option<BalanceOf<T>> {
+		// an amount out of the range of balance can never be paid.
+		let amount = (amount.bits() <= 128)
+			.then(|| amount.low_u128())
+			.and_then(|amount| BalanceOf::<T>::try_from(amount).ok());
+		let paid = amount.filter(|amount| {
+			T::Currency::transfer(winner, owner, *amount, ExistenceRequirement::AllowDeath).is_ok()
+		});
+		match paid {
+			Some(_) => {
+				T::Currency::unreserve(winner, bond);
+			},
+			None => {
+				let unpaid =
+					T::Currency::repatriate_reserved(winner, owner, bond, BalanceStatus::Free)
+						.unwrap_or(bond);
+				Self::deposit_event(Event::WinnerDefaulted(
+					auction_id,
+					winner.clone(),
+					bond.saturating_sub(unpaid),
+				));
+			},
+		}
+		paid
+	}
+}
@@ -0,0 +1,132 @@
+use crate as pallet_trex_auction;
+use frame_support::{parameter_types, traits::Everything};
+use frame_system as system;
+use pallet_trex::{RawReleasedKey, ReleasedKeys};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use std::{cell::RefCell, collections::BTreeMap};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+type Balance = u64;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Auction: pallet_trex_auction::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<Balance>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: Balance = 1;
+	pub const MaxLocks: u32 = 50;
+	pub const MaxReserves: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type Balance = Balance;
+	type DustRemoval = ();
+	type Event = Event;
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type MaxLocks = MaxLocks;
+	type MaxReserves = MaxReserves;
+	type ReserveIdentifier = [u8; 8];
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const AuctionDeposit: Balance = 10;
+	pub const MinBidBond: Balance = 5;
+	pub const RevealPeriod: u64 = 3;
+	pub const MaxBids: u32 = 3;
+}
+
+thread_local! {
+	/// The keys stored by the inherents of the blocks, by the height of the released block.
+	pub static RELEASED_KEYS: RefCell<BTreeMap<u64, RawReleasedKey>> = RefCell::new(BTreeMap::new());
+}
+
+pub struct MockReleasedKeys;
+
+impl MockReleasedKeys {
+	pub fn set(height: u64, key: RawReleasedKey) {
+		RELEASED_KEYS.with(|keys| keys.borrow_mut().insert(height, key));
+	}
+}
+
+impl ReleasedKeys<u64> for MockReleasedKeys {
+	fn released_key(height: u64) -> Option<RawReleasedKey> {
+		RELEASED_KEYS.with(|keys| keys.borrow().get(&height).cloned())
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn insert_released_key(height: u64, key: RawReleasedKey) {
+		Self::set(height, key)
+	}
+}
+
+impl pallet_trex_auction::Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type AuctionDeposit = AuctionDeposit;
+	type MinBidBond = MinBidBond;
+	type RevealPeriod = RevealPeriod;
+	type MaxBids = MaxBids;
+	type ReleasedKeys = MockReleasedKeys;
+	type AuctionWeight = ();
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 100), (2, 100), (3, 100), (4, 100), (5, 12)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,222 @@
+use crate::{mock::*, *};
+use frame_support::{assert_noop, assert_ok};
+
+/// A key over a 48 bit safe prime, which stands for the key released by the close block.
+fn released_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(98765432109u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+/// A sealed bid of an amount to the key of the close block.
+fn sealed(close: u32, amount: u64) -> KeyCapsule {
+	elgamal::encrypt(&released_key(), close, amount.into(), (987654 + amount).into())
+}
+
+/// Mine the block after the close block, which stores the key released by the close block.
+fn mine_after_close(close: u64) {
+	System::set_block_number(close + 1);
+	MockReleasedKeys::set(close, released_key());
+}
+
+fn bid_events() -> usize {
+	System::events()
+		.into_iter()
+		.filter(|record| {
+			matches!(record.event, mock::Event::Auction(crate::Event::BidRevealed(..)))
+		})
+		.count()
+}
+
+#[test]
+fn create_auction_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Auction::create_auction(Origin::signed(1), 10, 5));
+		assert_eq!(Auction::next_auction_id(), 1);
+		assert_eq!(
+			Auction::auctions(0),
+			Some(AuctionInfo { owner: 1, deposit: 10, close: 10, bid_bond: 5, bids: 0 })
+		);
+		assert_eq!(Balances::reserved_balance(1), 10);
+		System::assert_last_event(mock::Event::Auction(crate::Event::AuctionCreated(0, 1, 10)));
+
+		assert_noop!(
+			Auction::create_auction(Origin::signed(1), 1, 5),
+			Error::<Test>::CloseNotInFuture
+		);
+		assert_noop!(Auction::create_auction(Origin::signed(1), 10, 4), Error::<Test>::BondTooLow);
+		assert_noop!(
+			Auction::create_auction(Origin::signed(6), 10, 5),
+			Error::<Test>::InsufficientDeposit
+		);
+	});
+}
+
+#[test]
+fn place_bid_checks_the_sealed_bid() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Auction::create_auction(Origin::signed(1), 10, 5));
+
+		assert_noop!(
+			Auction::place_bid(Origin::signed(2), 1, sealed(10, 30)),
+			Error::<Test>::AuctionNotFound
+		);
+		assert_noop!(
+			Auction::place_bid(Origin::signed(2), 0, sealed(11, 30)),
+			Error::<Test>::InvalidTarget
+		);
+		let bid = sealed(10, 30);
+		for capsule in
+			[KeyCapsule { bit_length: 40, ..bid.clone() }, KeyCapsule { c1: bid.p, ..bid.clone() }]
+		{
+			assert_noop!(
+				Auction::place_bid(Origin::signed(2), 0, capsule),
+				Error::<Test>::InvalidCapsule
+			);
+		}
+		assert_noop!(
+			Auction::place_bid(Origin::signed(6), 0, sealed(10, 30)),
+			Error::<Test>::InsufficientBond
+		);
+
+		assert_ok!(Auction::place_bid(Origin::signed(2), 0, bid.clone()));
+		assert_eq!(
+			Auction::bids(0, 2),
+			Some(BidInfo { index: 0, bond: 5, capsule: bid, amount: None })
+		);
+		assert_eq!(Balances::reserved_balance(2), 5);
+		System::assert_last_event(mock::Event::Auction(crate::Event::BidPlaced(0, 2)));
+		assert_noop!(
+			Auction::place_bid(Origin::signed(2), 0, sealed(10, 40)),
+			Error::<Test>::AlreadyBid
+		);
+
+		assert_ok!(Auction::place_bid(Origin::signed(3), 0, sealed(10, 40)));
+		assert_ok!(Auction::place_bid(Origin::signed(4), 0, sealed(10, 50)));
+		assert_eq!(Auction::auctions(0).map(|auction| auction.bids), Some(3));
+		assert_noop!(
+			Auction::place_bid(Origin::signed(5), 0, sealed(10, 60)),
+			Error::<Test>::TooManyBids
+		);
+
+		System::set_block_number(10);
+		assert_noop!(
+			Auction::place_bid(Origin::signed(5), 0, sealed(10, 60)),
+			Error::<Test>::BiddingClosed
+		);
+	});
+}
+
+#[test]
+fn highest_revealed_bid_wins() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Auction::create_auction(Origin::signed(1), 5, 5));
+		for (who, amount) in [(2, 30), (3, 50), (4, 40)] {
+			assert_ok!(Auction::place_bid(Origin::signed(who), 0, sealed(5, amount)));
+		}
+
+		// anyone can reveal the bids once the key of the close block is stored.
+		mine_after_close(5);
+		let amounts = vec![(2, U256::from(30)), (3, U256::from(50)), (4, U256::from(40))];
+		assert_ok!(Auction::reveal_bids(Origin::signed(5), 0, amounts));
+		assert_eq!(Auction::bids(0, 3).and_then(|bid| bid.amount), Some(U256::from(50)));
+		assert_eq!(bid_events(), 3);
+
+		System::set_block_number(8);
+		assert_noop!(Auction::settle(Origin::signed(5), 0), Error::<Test>::RevealPeriodNotOver);
+		System::set_block_number(9);
+		assert_ok!(Auction::settle(Origin::signed(5), 0));
+		System::assert_last_event(mock::Event::Auction(crate::Event::AuctionSettled(
+			0,
+			Some((3, 50)),
+		)));
+
+		assert_eq!(Balances::free_balance(1), 150);
+		assert_eq!(Balances::free_balance(3), 50);
+		for who in 1..=4 {
+			assert_eq!(Balances::reserved_balance(who), 0);
+		}
+		assert_eq!(Auction::auctions(0), None);
+		assert_eq!(Bids::<Test>::iter_prefix(0).count(), 0);
+		assert_noop!(Auction::settle(Origin::signed(5), 0), Error::<Test>::AuctionNotFound);
+	});
+}
+
+#[test]
+fn reveal_bids_checks_the_released_key() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Auction::create_auction(Origin::signed(1), 5, 5));
+		assert_ok!(Auction::place_bid(Origin::signed(2), 0, sealed(5, 30)));
+		assert_ok!(Auction::place_bid(Origin::signed(3), 0, sealed(5, 50)));
+		let reveal =
+			|amounts: Vec<(u64, U256)>| Auction::reveal_bids(Origin::signed(4), 0, amounts);
+
+		// the key of the close block is stored by the next block.
+		System::set_block_number(6);
+		assert_noop!(reveal(vec![(2, 30.into())]), Error::<Test>::KeyNotReleased);
+
+		mine_after_close(5);
+		assert_noop!(reveal(vec![(2, 31.into())]), Error::<Test>::InvalidReveal);
+		// the reveals are checked before any of them is stored.
+		assert_noop!(reveal(vec![(2, 30.into()), (3, 51.into())]), Error::<Test>::InvalidReveal);
+		assert_noop!(reveal(vec![(4, 30.into())]), Error::<Test>::BidNotFound);
+
+		assert_ok!(reveal(vec![(2, 30.into())]));
+		assert_noop!(reveal(vec![(2, 30.into())]), Error::<Test>::AlreadyRevealed);
+
+		// the amount decrypted with a forged key is checked with the stored key.
+		let key = released_key();
+		let x = U256::from(12345u64);
+		let forged = RawReleasedKey { h: elgamal::pow_mod(key.g, x, key.p), x, ..key };
+		let bid = Auction::bids(0, 3).unwrap().capsule;
+		let forged_amount = elgamal::decrypt(&forged, &bid).unwrap();
+		assert_ne!(forged_amount, U256::from(50));
+		assert_noop!(reveal(vec![(3, forged_amount)]), Error::<Test>::InvalidReveal);
+
+		System::set_block_number(9);
+		assert_noop!(reveal(vec![(3, 50.into())]), Error::<Test>::RevealPeriodOver);
+	});
+}
+
+#[test]
+fn settle_breaks_ties_and_skips_unrevealed_bids() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Auction::create_auction(Origin::signed(1), 5, 5));
+		for (who, amount) in [(2, 40), (3, 40), (4, 90)] {
+			assert_ok!(Auction::place_bid(Origin::signed(who), 0, sealed(5, amount)));
+		}
+		mine_after_close(5);
+		let amounts = vec![(3, U256::from(40)), (2, U256::from(40))];
+		assert_ok!(Auction::reveal_bids(Origin::signed(1), 0, amounts));
+
+		System::set_block_number(9);
+		assert_ok!(Auction::settle(Origin::signed(1), 0));
+		// the earlier bid wins a tie, and the unrevealed bid is refunded.
+		System::assert_last_event(mock::Event::Auction(crate::Event::AuctionSettled(
+			0,
+			Some((2, 40)),
+		)));
+		assert_eq!(Balances::free_balance(2), 60);
+		assert_eq!(Balances::free_balance(3), 100);
+		assert_eq!(Balances::free_balance(4), 100);
+	});
+}
+
+#[test]
+fn defaulted_winner_forfeits_the_bond() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Auction::create_auction(Origin::signed(1), 5, 5));
+		assert_ok!(Auction::place_bid(Origin::signed(5), 0, sealed(5, 50)));
+		mine_after_close(5);
+		assert_ok!(Auction::reveal_bids(Origin::signed(1), 0, vec![(5, U256::from(50))]));
+
+		System::set_block_number(9);
+		assert_ok!(Auction::settle(Origin::signed(1), 0));
+		System::assert_has_event(mock::Event::Auction(crate::Event::WinnerDefaulted(0, 5, 5)));
+		System::assert_last_event(mock::Event::Auction(crate::Event::AuctionSettled(0, None)));
+		assert_eq!(Balances::free_balance(1), 105);
+		assert_eq!(Balances::free_balance(5), 7);
+		assert_eq!(Balances::reserved_balance(5), 0);
+	});
+}
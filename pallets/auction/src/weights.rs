@@ -0,0 +1,102 @@
+//! Weights for pallet_trex_auction
+//!
+//! Estimated from the storage accesses of the calls and the ElGamal arithmetic on 48 bit keys.
+//! Regenerate with the `benchmark` command of the node from the benchmarks of `benchmarking.rs`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_trex_auction.
+pub trait AuctionWeight {
+    fn create_auction() -> Weight;
+    fn place_bid() -> Weight;
+    fn reveal_bids(n: u32, ) -> Weight;
+    fn settle(b: u32, ) -> Weight;
+}
+
+/// Weights for pallet_trex_auction using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> AuctionWeight for SubstrateWeight<T> {
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXAuction NextAuctionId (r:1 w:1), Auctions (r:0 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn create_auction() -> Weight {
+        (40_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(3 as Weight))
+            .saturating_add(T::DbWeight::get().writes(3 as Weight))
+    }
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXAuction Auctions (r:1 w:1), Bids (r:1 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn place_bid() -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(4 as Weight))
+            .saturating_add(T::DbWeight::get().writes(3 as Weight))
+    }
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXAuction Auctions (r:1 w:0), Bids (r:1 w:1)
+    // Storage: TREXKeys Released (r:1 w:0)
+    fn reveal_bids(n: u32, ) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(T::DbWeight::get().reads(3 as Weight))
+            .saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(T::DbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
+    }
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXAuction Auctions (r:1 w:1), Bids (r:1 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn settle(b: u32, ) -> Weight {
+        (50_000_000 as Weight)
+            .saturating_add((20_000_000 as Weight).saturating_mul(b as Weight))
+            .saturating_add(T::DbWeight::get().reads(4 as Weight))
+            .saturating_add(T::DbWeight::get().reads((2 as Weight).saturating_mul(b as Weight)))
+            .saturating_add(T::DbWeight::get().writes(3 as Weight))
+            .saturating_add(T::DbWeight::get().writes((2 as Weight).saturating_mul(b as Weight)))
+    }
+}
+
+// For backwards compatibility and tests
+impl AuctionWeight for () {
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXAuction NextAuctionId (r:1 w:1), Auctions (r:0 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn create_auction() -> Weight {
+        (40_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(3 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(3 as Weight))
+    }
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXAuction Auctions (r:1 w:1), Bids (r:1 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn place_bid() -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(4 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(3 as Weight))
+    }
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXAuction Auctions (r:1 w:0), Bids (r:1 w:1)
+    // Storage: TREXKeys Released (r:1 w:0)
+    fn reveal_bids(n: u32, ) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(RocksDbWeight::get().reads(3 as Weight))
+            .saturating_add(RocksDbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(RocksDbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
+    }
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXAuction Auctions (r:1 w:1), Bids (r:1 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn settle(b: u32, ) -> Weight {
+        (50_000_000 as Weight)
+            .saturating_add((20_000_000 as Weight).saturating_mul(b as Weight))
+            .saturating_add(RocksDbWeight::get().reads(4 as Weight))
+            .saturating_add(RocksDbWeight::get().reads((2 as Weight).saturating_mul(b as Weight)))
+            .saturating_add(RocksDbWeight::get().writes(3 as Weight))
+            .saturating_add(RocksDbWeight::get().writes((2 as Weight).saturating_mul(b as Weight)))
+    }
+}
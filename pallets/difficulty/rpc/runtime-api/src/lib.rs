@@ -2,10 +2,17 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::unnecessary_mut_passed)]
 
+use codec::Codec;
+pub use pallet_difficulty::ReleaseEstimate;
+
 // Here we declare the runtime API. It is implemented it the `impl` block in
 // runtime amalgamator file (the `runtime/src/lib.rs`)
 sp_api::decl_runtime_apis! {
-	pub trait DiffAdjustmentApi{
+	/// The second version adds `estimate_release_block`.
+	#[api_version(2)]
+	pub trait DiffAdjustmentApi<BlockNumber> where BlockNumber: Codec {
 		fn get_avg_blocktime() -> u32;
+		/// Estimate the block mined at or after the timestamp in milliseconds.
+		fn estimate_release_block(timestamp: u64) -> Option<ReleaseEstimate<BlockNumber>>;
 	}
 }
@@ -6,16 +6,26 @@ use jsonrpsee::{
 	types::error::{CallError, ErrorCode, ErrorObject},
 };
 pub use pallet_difficulty_runtime_api::DiffAdjustmentApi as DiffAdjustmentRuntimeApi;
-use sp_api::ProvideRuntimeApi;
+use pallet_difficulty_runtime_api::ReleaseEstimate;
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
-use sp_runtime::{traits::{Block as BlockT}};
+use sp_runtime::traits::{Block as BlockT, NumberFor};
 use std::sync::Arc;
 use sp_runtime::generic::BlockId;
 
 #[rpc(server,client)]
-pub trait DiffAdjustmentApi<BlockHash> {
+pub trait DiffAdjustmentApi<BlockHash, BlockNumber> {
 	#[method(name = "difficulty_getAvgBlockTime")]
 	fn get_avg_blocktime(&self, at: Option<BlockHash>) -> RpcResult<u32>;
+
+	/// Estimate the height of the block mined at or after a unix timestamp in milliseconds,
+	/// together with a confidence interval of the height and the expected difficulty.
+	#[method(name = "trex_estimateReleaseBlock")]
+	fn estimate_release_block(
+		&self,
+		timestamp: u64,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<ReleaseEstimate<BlockNumber>>>;
 }
 
 pub struct DiffAdjustment<C, B> {
@@ -33,14 +43,14 @@ impl<C, B> DiffAdjustment<C, B> {
 }
 
 #[async_trait]
-impl<C,B> DiffAdjustmentApiServer<<B as BlockT>::Hash> for DiffAdjustment<C,B>
+impl<C,B> DiffAdjustmentApiServer<<B as BlockT>::Hash, NumberFor<B>> for DiffAdjustment<C,B>
 	where
 		B: BlockT + 'static,
 		C: HeaderBackend<B> + ProvideRuntimeApi<B>
 		+ Send
 		+ Sync
 		+ 'static,
-		C::Api: DiffAdjustmentRuntimeApi<B>,
+		C::Api: DiffAdjustmentRuntimeApi<B, NumberFor<B>>,
 {
 	fn get_avg_blocktime(&self,at: Option<<B as BlockT>::Hash>) -> RpcResult<u32> {
 		let api = self.client.runtime_api();
@@ -57,4 +67,39 @@ impl<C,B> DiffAdjustmentApiServer<<B as BlockT>::Hash> for DiffAdjustment<C,B>
 			)))
 		})
 	}
-}
\ No newline at end of file
+
+	fn estimate_release_block(
+		&self,
+		timestamp: u64,
+		at: Option<<B as BlockT>::Hash>,
+	) -> RpcResult<Option<ReleaseEstimate<NumberFor<B>>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		let version = api
+			.api_version::<dyn DiffAdjustmentRuntimeApi<B, NumberFor<B>>>(&at)
+			.map_err(|e| {
+				JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+					ErrorCode::InternalError.code(),
+					format!("Unable to query the runtime API version: {:?}", e),
+					None::<()>,
+				)))
+			})?;
+		if version.map_or(true, |version| version < 2) {
+			return Err(JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+				ErrorCode::MethodNotFound.code(),
+				"The runtime does not support release block estimation",
+				None::<()>,
+			))))
+		}
+		api.estimate_release_block(&at, timestamp).map_err(|e| {
+			JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+				ErrorCode::InternalError.code(),
+				format!("Unable to estimate the release block: {:?}", e),
+				None::<()>,
+			)))
+		})
+	}
+}
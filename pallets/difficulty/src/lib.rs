@@ -10,8 +10,21 @@ use frame_support::traits::{OnTimestampSet,Get};
 #[cfg(not(feature = "std"))]
 use num_traits::float::FloatCore;
 pub use pallet::*;
-use sp_runtime::traits::UniqueSaturatedInto;
-use sp_std::cmp::{max, min};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{
+	traits::{IntegerSquareRoot, UniqueSaturatedInto},
+	RuntimeDebug,
+};
+use sp_std::{
+	cmp::{max, min},
+	vec::Vec,
+};
+
+#[cfg(test)]
+mod mock;
 
 #[cfg(test)]
 mod tests;
@@ -22,6 +35,72 @@ pub fn clamp(block_time_target: Difficulty, measured_block_time: Difficulty) ->
 	max(min(adjustment as i128, CLAMP_FACTOR as i128), -(CLAMP_FACTOR as i128))
 }
 
+/// Estimated height of the first block mined after some wall-clock time.
+#[derive(Encode, Decode, TypeInfo, RuntimeDebug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ReleaseEstimate<BlockNumber> {
+	/// Most likely height of the block, whose key is released at the given time.
+	pub height: BlockNumber,
+	/// Lower bound of the ~95% confidence interval.
+	pub lower: BlockNumber,
+	/// Upper bound of the ~95% confidence interval.
+	pub upper: BlockNumber,
+	/// Expected block time at the current difficulty in milliseconds.
+	pub block_time: u64,
+	/// The current difficulty, which is the bit length of the key expected to be mined at the
+	/// block. The public key itself is only committed once the parent of the block is mined.
+	pub difficulty: Difficulty,
+}
+
+/// Scale a block time measured at one difficulty to the expected block time at another one,
+/// the work of pollard rho grows by a factor of sqrt(2) for each bit of difficulty.
+pub fn scale_block_time(time: u128, from: Difficulty, to: Difficulty) -> u128 {
+	let (steps, up) = if to >= from { (to - from, true) } else { (from - to, false) };
+	let mut time = if up {
+		time.saturating_mul(1u128 << min(steps / 2, 127))
+	} else {
+		time >> min(steps / 2, 127)
+	};
+	if steps % 2 == 1 {
+		time = if up { time.saturating_mul(1414) / 1000 } else { time.saturating_mul(1000) / 1414 };
+	}
+	time
+}
+
+/// Mean and standard deviation of the block times (milliseconds) between consecutive samples of
+/// timestamp and difficulty, normalized to the current difficulty.
+///
+/// Falls back to the target block time when there is no history, the block time of PoW mining is
+/// roughly exponential distributed so the deviation is as large as the mean.
+pub fn block_time_stats(
+	mut samples: Vec<(u128, Difficulty)>,
+	current: Difficulty,
+	target: u128,
+) -> (u128, u128) {
+	samples.sort_by_key(|(timestamp, _)| *timestamp);
+	let deltas: Vec<u128> = samples
+		.windows(2)
+		.map(|w| scale_block_time(w[1].0.saturating_sub(w[0].0), w[1].1, current))
+		.filter(|delta| *delta > 0)
+		.collect();
+	if deltas.is_empty() {
+		let target = max(target, 1);
+		return (target, target)
+	}
+	let count = deltas.len() as u128;
+	let mean = max(deltas.iter().fold(0u128, |acc, d| acc.saturating_add(*d)) / count, 1);
+	let variance = deltas
+		.iter()
+		.map(|d| {
+			let diff = if *d > mean { d - mean } else { mean - d };
+			diff.saturating_mul(diff)
+		})
+		.fold(0u128, |acc, v| acc.saturating_add(v)) /
+		count;
+	(mean, variance.integer_sqrt())
+}
+
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
@@ -38,7 +117,7 @@ pub mod pallet {
 	/// Configure the pallet by specifying the parameters and types on which it depends.
 	#[pallet::config]
 	pub trait Config: frame_system::Config + pallet_timestamp::Config {
-		/// Target block time in millseconds.
+		/// Target block time in seconds.
 		#[pallet::constant]
 		type TargetBlockTime: Get<Self::Moment>;
 	}
@@ -197,3 +276,44 @@ impl<T: Config> Pallet<T> {
 		(ts_delta / DIFFICULTY_ADJUST_WINDOW as u128) as u32
 	}
 }
+
+impl<T: Config> Pallet<T> {
+	/// Estimate the height of the first block mined at or after the given timestamp (in
+	/// milliseconds), based on the block times of the last adjustment window and the current
+	/// difficulty. Returns `None` if the timestamp is not in the future.
+	pub fn estimate_release_block(timestamp: T::Moment) -> Option<ReleaseEstimate<T::BlockNumber>> {
+		let now: u128 = pallet_timestamp::Pallet::<T>::get().unique_saturated_into();
+		let timestamp: u128 = timestamp.unique_saturated_into();
+		if timestamp <= now {
+			return None
+		}
+		let current = Self::difficulty().unwrap_or(INIT_DIFFICULTY);
+		// the target block time is configured in seconds.
+		let target =
+			UniqueSaturatedInto::<u128>::unique_saturated_into(T::TargetBlockTime::get()) * 1000;
+		// the window is a ring buffer, so it always holds the most recent blocks.
+		let samples = PastDifficultiesAndTimestamps::<T>::get()
+			.iter()
+			.flatten()
+			.map(|d| (d.timestamp.unique_saturated_into(), d.difficulty))
+			.collect();
+		let (mean, deviation) = block_time_stats(samples, current, target);
+
+		let span = timestamp - now;
+		let blocks = (span + mean - 1) / mean;
+		// the count of blocks mined in a span has a deviation of sigma * sqrt(n) / mean.
+		let margin = (2 * deviation).saturating_mul(blocks.integer_sqrt() + 1) / mean;
+
+		let number: u128 = frame_system::Pallet::<T>::block_number().unique_saturated_into();
+		let height = number.saturating_add(blocks);
+		let lower = max(height.saturating_sub(margin), number.saturating_add(1));
+		let upper = height.saturating_add(margin);
+		Some(ReleaseEstimate {
+			height: height.unique_saturated_into(),
+			lower: lower.unique_saturated_into(),
+			upper: upper.unique_saturated_into(),
+			block_time: mean.unique_saturated_into(),
+			difficulty: current,
+		})
+	}
+}
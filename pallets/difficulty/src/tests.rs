@@ -1,8 +1,73 @@
-use crate::clamp;
+use crate::{
+	block_time_stats, clamp, mock::*, scale_block_time, CurrentDifficulty, ReleaseEstimate,
+};
+use trex_constants::INIT_DIFFICULTY;
 
 #[test]
 fn test_clamp_algo(){
 	let result = clamp(3600,3100);
 	println!("result for clamp {}",&result);
 	assert_eq!(1,result,"Difficulty does not need to increase by 1");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_scale_block_time(){
+	assert_eq!(scale_block_time(60_000, 56, 56), 60_000);
+	assert_eq!(scale_block_time(60_000, 56, 58), 120_000);
+	assert_eq!(scale_block_time(60_000, 58, 56), 30_000);
+	assert_eq!(scale_block_time(60_000, 56, 57), 84_840);
+}
+
+#[test]
+fn test_block_time_stats(){
+	// no history falls back to the target block time.
+	assert_eq!(block_time_stats(vec![], 56, 60_000), (60_000, 60_000));
+	// samples out of order, as stored in the ring buffer of the difficulty window.
+	let samples = vec![(120_000, 56), (180_000, 56), (0, 56), (60_000, 56)];
+	assert_eq!(block_time_stats(samples, 56, 1), (60_000, 0));
+	// block times mined at a lower difficulty are scaled up.
+	let samples = vec![(0, 54), (30_000, 54), (60_000, 54)];
+	assert_eq!(block_time_stats(samples, 56, 1), (60_000, 0));
+}
+
+#[test]
+fn estimate_release_block_without_history(){
+	new_test_ext().execute_with(|| {
+		pallet_timestamp::Now::<Test>::put(1_000_000);
+		assert_eq!(DifficultyModule::estimate_release_block(1_000_000), None);
+		// the target block time of 60 seconds, with a deviation as large as the mean.
+		assert_eq!(
+			DifficultyModule::estimate_release_block(1_600_000),
+			Some(ReleaseEstimate {
+				height: 11,
+				lower: 3,
+				upper: 19,
+				block_time: 60_000,
+				difficulty: INIT_DIFFICULTY,
+			})
+		);
+	});
+}
+
+#[test]
+fn estimate_release_block_from_history(){
+	new_test_ext().execute_with(|| {
+		// blocks of 30 seconds mined at a difficulty 2 bits lower than the current one.
+		CurrentDifficulty::<Test>::put(54);
+		for k in 1..=5u64 {
+			Timestamp::set_timestamp(k * 30_000);
+		}
+		CurrentDifficulty::<Test>::put(56);
+		System::set_block_number(5);
+		assert_eq!(
+			DifficultyModule::estimate_release_block(150_000 + 300_000),
+			Some(ReleaseEstimate {
+				height: 10,
+				lower: 10,
+				upper: 10,
+				block_time: 60_000,
+				difficulty: 56,
+			})
+		);
+	});
+}
@@ -0,0 +1,39 @@
+//! Benchmarking setup for pallet-trex-keychain
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Keychain;
+use codec::Encode;
+use frame_benchmarking::benchmarks;
+use frame_support::traits::Hooks;
+use sp_core::U256;
+use sp_runtime::DigestItem;
+use sp_std::vec;
+
+/// The keychain digest of a 48 bit public key.
+fn bench_digest() -> KeychainDigest {
+	let pubkey = RawPublicKey {
+		bit_length: 48,
+		p: U256::from(140737488356903u64),
+		g: U256::from(5u64),
+		h: U256::from(42u64),
+	};
+	KeychainDigest::new(pubkey, &[1u8; 32])
+}
+
+benchmarks! {
+	// The keychain digest follows the author digest of the PoW engine, as in a mined block.
+	on_initialize {
+		frame_system::Pallet::<T>::deposit_log(DigestItem::PreRuntime(*b"pow_", vec![1u8; 32]));
+		let item = DigestItem::PreRuntime(TREX_ENGINE_ID, bench_digest().encode());
+		frame_system::Pallet::<T>::deposit_log(item);
+	}: {
+		Keychain::<T>::on_initialize(1u32.into());
+	}
+	verify {
+		assert_eq!(CurrentPubkey::<T>::get(), Some(bench_digest().pubkey));
+	}
+
+	impl_benchmark_test_suite!(Keychain, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,70 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Keychain state of the mined blocks of TREX.
+//!
+//! The seal of a block carries the public key mined at the block and the seeds of the keychain of
+//! the next block, but it is a post-runtime digest which the runtime can not read. The miner also
+//! puts the public key and a commitment to the seeds in a pre-runtime digest with
+//! `TREX_ENGINE_ID`, and the importing nodes reject a block whose digest does not match its seal.
+//!
+//! The pallet reads the digest when the block is initialized and stores the keychain state of the
+//! current block, which is exposed to the other pallets and by the `KeychainApi` runtime API.
+pub use pallet::*;
+pub use trex_keychain::{KeychainDigest, RawPublicKey, TREX_ENGINE_ID};
+pub mod weights;
+pub use weights::KeychainWeight;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+	use sp_core::H256;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Weight information for extrinsics in this pallet.
+		type KeychainWeight: KeychainWeight;
+	}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	/// The public key mined at the current block.
+	#[pallet::storage]
+	#[pallet::getter(fn current_pubkey)]
+	pub type CurrentPubkey<T: Config> = StorageValue<_, RawPublicKey>;
+
+	/// The commitment to the seeds of the keychain of the next block.
+	#[pallet::storage]
+	#[pallet::getter(fn seeds_commitment)]
+	pub type SeedsCommitment<T: Config> = StorageValue<_, H256>;
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Store the keychain state in the pre-runtime digest of the block, the state of the parent
+		/// is cleared if the block has no keychain digest.
+		fn on_initialize(_: T::BlockNumber) -> Weight {
+			match KeychainDigest::find(&frame_system::Pallet::<T>::digest()) {
+				Some(digest) => {
+					CurrentPubkey::<T>::put(digest.pubkey);
+					SeedsCommitment::<T>::put(digest.seeds_commitment);
+				},
+				None => {
+					CurrentPubkey::<T>::kill();
+					SeedsCommitment::<T>::kill();
+				},
+			}
+			T::KeychainWeight::on_initialize()
+		}
+	}
+}
@@ -0,0 +1,53 @@
+use crate::{mock::*, *};
+use codec::Encode;
+use frame_support::traits::Hooks;
+use sp_core::{H256, U256};
+use sp_runtime::{Digest, DigestItem};
+
+/// The keychain digest of a 48 bits public key and some seeds.
+fn keychain_digest(h: u64) -> KeychainDigest {
+	let pubkey = RawPublicKey {
+		bit_length: 48,
+		p: U256::from(140737488356903u64),
+		g: U256::from(5u64),
+		h: U256::from(h),
+	};
+	KeychainDigest::new(pubkey, &[1u8; 32])
+}
+
+/// Initialize a block with some digest items as the executive does.
+fn initialize(number: u64, logs: Vec<DigestItem>) {
+	System::initialize(&number, &H256::repeat_byte(number as u8), &Digest { logs });
+	Keychain::on_initialize(number);
+}
+
+#[test]
+fn keychain_state_is_read_from_the_digest() {
+	new_test_ext().execute_with(|| {
+		let digest = keychain_digest(42);
+		// the author digest of the PoW engine is next to the keychain digest.
+		let author = DigestItem::PreRuntime(*b"pow_", vec![1u8; 32]);
+		initialize(2, vec![author, DigestItem::PreRuntime(TREX_ENGINE_ID, digest.encode())]);
+		assert_eq!(Keychain::current_pubkey(), Some(digest.pubkey.clone()));
+		assert_eq!(Keychain::seeds_commitment(), Some(digest.seeds_commitment));
+
+		let next = keychain_digest(43);
+		initialize(3, vec![DigestItem::PreRuntime(TREX_ENGINE_ID, next.encode())]);
+		assert_eq!(Keychain::current_pubkey(), Some(next.pubkey));
+		assert_eq!(Keychain::seeds_commitment(), Some(next.seeds_commitment));
+	});
+}
+
+#[test]
+fn keychain_state_is_cleared_without_the_digest() {
+	new_test_ext().execute_with(|| {
+		let digest = keychain_digest(42);
+		initialize(2, vec![DigestItem::PreRuntime(TREX_ENGINE_ID, digest.encode())]);
+		assert!(Keychain::current_pubkey().is_some());
+
+		// a malformed digest is ignored.
+		initialize(3, vec![DigestItem::PreRuntime(TREX_ENGINE_ID, vec![1u8; 4])]);
+		assert_eq!(Keychain::current_pubkey(), None);
+		assert_eq!(Keychain::seeds_commitment(), None);
+	});
+}
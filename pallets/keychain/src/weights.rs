@@ -0,0 +1,41 @@
+//! Weights for pallet_trex_keychain
+//!
+//! Not measured: the figure only counts the read of the digest, the two writes of the keychain state
+//! and a guess for decoding the digest. `on_initialize` in `benchmarking.rs` gives the real cost.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_trex_keychain.
+pub trait KeychainWeight {
+    fn on_initialize() -> Weight;
+}
+
+/// Weights for pallet_trex_keychain using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> KeychainWeight for SubstrateWeight<T> {
+    // Storage: System Digest (r:1 w:0)
+    // Storage: TREXKeychain CurrentPubkey (r:0 w:1)
+    // Storage: TREXKeychain SeedsCommitment (r:0 w:1)
+    fn on_initialize() -> Weight {
+        (8_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(1 as Weight))
+            .saturating_add(T::DbWeight::get().writes(2 as Weight))
+    }
+}
+
+// For backwards compatibility and tests
+impl KeychainWeight for () {
+    // Storage: System Digest (r:1 w:0)
+    // Storage: TREXKeychain CurrentPubkey (r:0 w:1)
+    // Storage: TREXKeychain SeedsCommitment (r:0 w:1)
+    fn on_initialize() -> Weight {
+        (8_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(1 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(2 as Weight))
+    }
+}
@@ -0,0 +1,33 @@
+//! Benchmarking setup for pallet-trex-keys
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Keys;
+use frame_benchmarking::benchmarks;
+use frame_system::RawOrigin;
+use sp_core::U256;
+
+/// A key over a 48 bit safe prime, the check of `g^x = h mod p` dominates the call.
+fn bench_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(98765432109u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+benchmarks! {
+	// The worst case removes the key which falls out of the retention window.
+	set_released_key {
+		let expired: T::BlockNumber = One::one();
+		let release = expired.saturating_add(T::KeyRetention::get());
+		Released::<T>::insert(expired, bench_key());
+		frame_system::Pallet::<T>::set_block_number(release.saturating_add(One::one()));
+	}: _(RawOrigin::None, bench_key())
+	verify {
+		assert!(Released::<T>::contains_key(release));
+		assert!(!Released::<T>::contains_key(expired));
+	}
+
+	impl_benchmark_test_suite!(Keys, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,137 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Keys released by the mined blocks of TREX.
+//!
+//! The seal of a block releases the private key of the public key mined at the block, which is
+//! derived from the solutions of its proof of work. The runtime can not read the seals, so the
+//! author of each block provides the key released by the seal of its parent with the trex
+//! inherent, and **set_released_key** stores it by the height of the parent.
+//!
+//! The importing nodes derive the key from the parent seal themselves, the inherent is rejected
+//! unless its key has the public key of the parent and `g^x = h mod p`. The other pallets read
+//! the stored keys through the `ReleasedKeys` trait of `pallet_trex`. A key is kept for
+//! `KeyRetention` blocks, after which the archive of the nodes serves it.
+use frame_support::inherent::{InherentData, InherentIdentifier, ProvideInherent};
+pub use pallet::*;
+pub use pallet_trex::{elgamal, RawReleasedKey, ReleasedKeys};
+use sp_runtime::traits::{CheckedSub, One, Saturating};
+pub use trex_inherent::{InherentError, TrexInherentData, INHERENT_IDENTIFIER};
+pub mod weights;
+pub use weights::KeysWeight;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Number of blocks a released key is stored, the key of an older block is removed.
+		#[pallet::constant]
+		type KeyRetention: Get<Self::BlockNumber>;
+
+		/// Weight information for extrinsics in this pallet.
+		type KeysWeight: KeysWeight;
+	}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	/// The keys released by the mined blocks, by the height of the block releasing them.
+	#[pallet::storage]
+	#[pallet::getter(fn released)]
+	pub type Released<T: Config> = StorageMap<_, Twox64Concat, T::BlockNumber, RawReleasedKey>;
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The key of the parent block is already stored.
+		KeyAlreadySet,
+		/// The private key does not match its public key.
+		InvalidReleasedKey,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Store the key released by the parent block and remove the key which falls out of the
+		/// retention window, this is an inherent provided by the block author.
+		#[pallet::weight((T::KeysWeight::set_released_key(), DispatchClass::Mandatory))]
+		pub fn set_released_key(origin: OriginFor<T>, key: RawReleasedKey) -> DispatchResult {
+			ensure_none(origin)?;
+			let release = frame_system::Pallet::<T>::block_number().saturating_sub(One::one());
+			ensure!(!Released::<T>::contains_key(release), Error::<T>::KeyAlreadySet);
+			ensure!(elgamal::verify_key(&key), Error::<T>::InvalidReleasedKey);
+
+			Released::<T>::insert(release, key);
+			if let Some(expired) = release.checked_sub(&T::KeyRetention::get()) {
+				Released::<T>::remove(expired);
+			}
+			Ok(())
+		}
+	}
+
+	#[pallet::inherent]
+	impl<T: Config> ProvideInherent for Pallet<T> {
+		type Call = Call<T>;
+		type Error = InherentError;
+		const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+		fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+			// the genesis block is not mined, so it releases no key.
+			if frame_system::Pallet::<T>::block_number() <= One::one() {
+				return None
+			}
+			let key = data.released_key().ok().flatten()?;
+			Some(Call::set_released_key { key })
+		}
+
+		fn is_inherent_required(_: &InherentData) -> Result<Option<Self::Error>, Self::Error> {
+			// the inherents are checked on the state of the parent block, every mined parent
+			// releases a key.
+			let required = frame_system::Pallet::<T>::block_number() >= One::one();
+			Ok(required.then(|| InherentError::MissingReleasedKey))
+		}
+
+		fn check_inherent(call: &Self::Call, data: &InherentData) -> Result<(), Self::Error> {
+			let key = match call {
+				Call::set_released_key { key } => key,
+				_ => return Ok(()),
+			};
+			// the key derived by the importing node from the parent seal has the public key of
+			// the parent, a node which can not derive it rejects the key, as the genesis block
+			// releases none.
+			let parent =
+				data.released_key().ok().flatten().ok_or(InherentError::MissingReleasedKey)?;
+			let same_pubkey = (parent.bit_length, parent.p, parent.g, parent.h) ==
+				(key.bit_length, key.p, key.g, key.h);
+			if !same_pubkey || !elgamal::verify_key(key) {
+				return Err(InherentError::InvalidReleasedKey)
+			}
+			Ok(())
+		}
+
+		fn is_inherent(call: &Self::Call) -> bool {
+			matches!(call, Call::set_released_key { .. })
+		}
+	}
+}
+
+impl<T: Config> ReleasedKeys<T::BlockNumber> for Pallet<T> {
+	fn released_key(height: T::BlockNumber) -> Option<RawReleasedKey> {
+		Released::<T>::get(height)
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn insert_released_key(height: T::BlockNumber, key: RawReleasedKey) {
+		Released::<T>::insert(height, key)
+	}
+}
@@ -0,0 +1,94 @@
+use crate::{mock::*, *};
+use frame_support::{assert_noop, assert_ok, error::BadOrigin};
+use sp_core::U256;
+
+/// A key over a 48 bit safe prime with the private key `x`, which stands for a released key.
+fn released_key(x: u64) -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(x);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+fn inherent_data(key: Option<RawReleasedKey>) -> InherentData {
+	let mut data = InherentData::new();
+	if let Some(key) = key {
+		data.put_data(INHERENT_IDENTIFIER, &key).unwrap();
+	}
+	data
+}
+
+#[test]
+fn released_key_is_stored_by_the_parent_height() {
+	new_test_ext().execute_with(|| {
+		let key = released_key(98765432109);
+		System::set_block_number(2);
+		assert_noop!(Keys::set_released_key(Origin::signed(1), key.clone()), BadOrigin);
+		let wrong_key = RawReleasedKey { x: key.x + 1, ..key.clone() };
+		assert_noop!(
+			Keys::set_released_key(Origin::none(), wrong_key),
+			Error::<Test>::InvalidReleasedKey
+		);
+
+		assert_ok!(Keys::set_released_key(Origin::none(), key.clone()));
+		assert_eq!(Keys::released(1), Some(key.clone()));
+		assert_eq!(<Keys as ReleasedKeys<u64>>::released_key(1), Some(key));
+		assert_eq!(<Keys as ReleasedKeys<u64>>::released_key(2), None);
+		// a block stores a single key.
+		assert_noop!(
+			Keys::set_released_key(Origin::none(), released_key(12345)),
+			Error::<Test>::KeyAlreadySet
+		);
+	});
+}
+
+#[test]
+fn inherent_is_required_after_the_first_block() {
+	new_test_ext().execute_with(|| {
+		let data = inherent_data(Some(released_key(98765432109)));
+		// the genesis block releases no key.
+		System::set_block_number(0);
+		assert_eq!(Keys::is_inherent_required(&data), Ok(None));
+		System::set_block_number(1);
+		assert_eq!(Keys::create_inherent(&data), None);
+		assert_eq!(Keys::is_inherent_required(&data), Ok(Some(InherentError::MissingReleasedKey)));
+
+		System::set_block_number(2);
+		assert_eq!(Keys::create_inherent(&inherent_data(None)), None);
+		let call = Keys::create_inherent(&data).unwrap();
+		assert_eq!(call, crate::Call::set_released_key { key: released_key(98765432109) });
+		assert!(Keys::is_inherent(&call));
+	});
+}
+
+#[test]
+fn check_inherent_verifies_the_key_of_the_parent() {
+	new_test_ext().execute_with(|| {
+		let key = released_key(98765432109);
+		let check = |key: RawReleasedKey, parent: Option<RawReleasedKey>| {
+			Keys::check_inherent(&crate::Call::set_released_key { key }, &inherent_data(parent))
+		};
+
+		assert_ok!(check(key.clone(), Some(key.clone())));
+		// a key can not be checked without the key derived from the parent seal.
+		assert_eq!(check(key.clone(), None), Err(InherentError::MissingReleasedKey));
+		let wrong_key = RawReleasedKey { x: key.x + 1, ..key.clone() };
+		assert_eq!(check(wrong_key, Some(key.clone())), Err(InherentError::InvalidReleasedKey));
+		// a valid key of another public key is not the key of the parent.
+		assert_eq!(check(released_key(12345), Some(key)), Err(InherentError::InvalidReleasedKey));
+	});
+}
+
+#[test]
+fn released_keys_are_pruned_after_the_retention() {
+	new_test_ext().execute_with(|| {
+		for now in 2..=12 {
+			System::set_block_number(now);
+			assert_ok!(Keys::set_released_key(Origin::none(), released_key(now)));
+		}
+		// the keys of the last `KeyRetention` blocks are kept.
+		assert_eq!(Keys::released(1), None);
+		assert_eq!(Keys::released(2), Some(released_key(3)));
+		assert_eq!(Keys::released(11), Some(released_key(12)));
+	});
+}
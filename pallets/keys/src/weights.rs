@@ -0,0 +1,39 @@
+//! Weights for pallet_trex_keys
+//!
+//! Hand written from the storage accesses of the inherent and one modular exponentiation on a 48 bit
+//! key. The figures are placeholders until `set_released_key` of `benchmarking.rs` is run.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_trex_keys.
+pub trait KeysWeight {
+    fn set_released_key() -> Weight;
+}
+
+/// Weights for pallet_trex_keys using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> KeysWeight for SubstrateWeight<T> {
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXKeys Released (r:1 w:2)
+    fn set_released_key() -> Weight {
+        (25_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(2 as Weight))
+    }
+}
+
+// For backwards compatibility and tests
+impl KeysWeight for () {
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXKeys Released (r:1 w:2)
+    fn set_released_key() -> Weight {
+        (25_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(2 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(2 as Weight))
+    }
+}
@@ -0,0 +1,63 @@
+//! Benchmarking setup for pallet-trex-mempool
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Mempool;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+use sp_core::U256;
+use sp_std::vec;
+
+/// The block including the benchmarked calls.
+const INCLUDED: u32 = 10;
+
+/// A key over a 48 bit safe prime, the cost of the arithmetic is dominated by the big integers.
+fn bench_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(98765432109u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+/// Seal a payload of `l` bytes in total to the key of the including block, the payload is not a
+/// call so it is dropped once it is opened.
+fn sealed(l: u32) -> (KeyCapsule, Vec<u8>) {
+	let secret = U256::from(424242u64);
+	let capsule = elgamal::encrypt(&bench_key(), INCLUDED, secret, U256::from(987654u64));
+	let payload = vec![0xff; l as usize - NONCE_LEN - TAG_LEN];
+	(capsule.clone(), envelope::seal(&capsule, secret, [7u8; NONCE_LEN], &payload))
+}
+
+benchmarks! {
+	submit_encrypted {
+		let l in (NONCE_LEN + TAG_LEN) as u32 .. T::MaxCallLen::get();
+		frame_system::Pallet::<T>::set_block_number(INCLUDED.into());
+		let caller: T::AccountId = whitelisted_caller();
+		let (capsule, ciphertext) = sealed(l);
+	}: _(RawOrigin::Signed(caller), capsule, ciphertext, 0)
+	verify {
+		assert_eq!(Pending::<T>::decode_len(T::BlockNumber::from(INCLUDED)), Some(1));
+	}
+
+	// The worst case opens `n` wrapped calls of the maximum length, the weights of the dispatched
+	// calls are added by the pallet.
+	execute_encrypted {
+		let n in 1 .. T::MaxPending::get();
+		frame_system::Pallet::<T>::set_block_number(INCLUDED.into());
+		for i in 0 .. n {
+			let submitter: T::AccountId = account("submitter", i, 0);
+			let (capsule, ciphertext) = sealed(T::MaxCallLen::get());
+			let origin = RawOrigin::Signed(submitter).into();
+			Mempool::<T>::submit_encrypted(origin, capsule, ciphertext, 0)
+				.expect("the wrapped call fits the limits");
+		}
+		frame_system::Pallet::<T>::set_block_number((INCLUDED + 1).into());
+		T::ReleasedKeys::insert_released_key(INCLUDED.into(), bench_key());
+	}: _(RawOrigin::None)
+	verify {
+		assert!(!Pending::<T>::contains_key(T::BlockNumber::from(INCLUDED)));
+	}
+
+	impl_benchmark_test_suite!(Mempool, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,362 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Encrypted mempool of TREX, which hides the transactions from the other users until they are
+//! included in a block.
+//!
+//! The public key mined in a block is committed by the seal of its parent, and its private key is
+//! released as soon as the block is mined. A transaction wrapped by **submit_encrypted** is sealed
+//! to the key of the block including it, as an envelope of `trex-client`, so nobody can read it
+//! before the block is mined. The miner of the block learns the key by mining it, and can mine a
+//! sibling block to the same key with its own transactions first, so the wrappers are not hidden
+//! from the miner of their block. The key released by the block is stored by the inherent
+//! of `pallet_trex_keys` in the next block, then the inherent of this pallet decrypts and
+//! dispatches the wrapped calls in the order of their inclusion, each with the origin of its
+//! submitter.
+//!
+//! A wrapper is charged by its length and its declared weight bound, so the transaction pool
+//! checks its fee without seeing the inner call. The `CheckEncryptedCall` signed extension checks
+//! the wrapper in the pool and limits its longevity to the block whose key it targets.
+use codec::{Decode, DecodeLimit, Encode};
+use frame_support::{
+	dispatch::{Dispatchable, GetDispatchInfo, PostDispatchInfo},
+	inherent::{InherentData, InherentIdentifier, MakeFatalError, ProvideInherent},
+	traits::{Get, IsSubType},
+	weights::{extract_actual_weight, DispatchInfo, Weight},
+};
+pub use pallet::*;
+use pallet_trex::envelope::{NONCE_LEN, TAG_LEN};
+pub use pallet_trex::{elgamal, envelope, KeyCapsule, RawReleasedKey, ReleasedKeys};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, One, Saturating, SignedExtension},
+	transaction_validity::{
+		InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+};
+use sp_std::{marker::PhantomData, vec::Vec};
+pub mod weights;
+pub use weights::MempoolWeight;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Maximum depth of the nested calls in a wrapped call, the same as the extrinsics.
+const MAX_CALL_DEPTH: u32 = 256;
+
+/// The identifier of the inherent executing the wrapped calls.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"trexmemp";
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The runtime call which can be wrapped.
+		type Call: Parameter
+			+ Dispatchable<Origin = Self::Origin, PostInfo = PostDispatchInfo>
+			+ GetDispatchInfo
+			+ IsType<<Self as frame_system::Config>::Call>;
+
+		/// Maximum length of a wrapped call in bytes, including its nonce.
+		#[pallet::constant]
+		type MaxCallLen: Get<u32>;
+
+		/// Maximum number of wrapped calls included in a block.
+		#[pallet::constant]
+		type MaxPending: Get<u32>;
+
+		/// Maximum total weight bound of the wrapped calls included in a block, which is reserved
+		/// for the inherent dispatching them in the next block.
+		#[pallet::constant]
+		type MaxPendingWeight: Get<Weight>;
+
+		/// The keys released by the mined blocks, which open the wrapped calls.
+		type ReleasedKeys: ReleasedKeys<Self::BlockNumber>;
+
+		/// Weight information for extrinsics in this pallet.
+		type MempoolWeight: MempoolWeight;
+	}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	/// A call sealed to the key of the block including it.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct EncryptedCall<AccountId, Ciphertext> {
+		/// Account which submitted the call, which is the origin of the dispatch.
+		pub submitter: AccountId,
+		/// Maximum weight of the call, a heavier call is dropped.
+		pub weight_bound: Weight,
+		/// The secret of the symmetric key encrypted to the key of the including block.
+		pub capsule: KeyCapsule,
+		/// The nonce followed by the encrypted call.
+		pub ciphertext: Ciphertext,
+	}
+
+	pub type EncryptedCallOf<T> = EncryptedCall<
+		<T as frame_system::Config>::AccountId,
+		BoundedVec<u8, <T as Config>::MaxCallLen>,
+	>;
+
+	/// The wrapped calls by the block including them, in the order of inclusion. They are taken
+	/// by the inherent of the next block once the key of the block is released.
+	#[pallet::storage]
+	#[pallet::getter(fn pending)]
+	pub type Pending<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<EncryptedCallOf<T>, T::MaxPending>,
+		ValueQuery,
+	>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A wrapped call is included. \[submitter, block, index\]
+		EncryptedCallSubmitted(T::AccountId, T::BlockNumber, u32),
+		/// A wrapped call is decrypted and dispatched. \[block, index, result\]
+		EncryptedCallDispatched(T::BlockNumber, u32, DispatchResult),
+		/// A wrapped call can not be opened with the released key, it is not a valid call, or it
+		/// exceeds its weight bound, so it is dropped. \[block, index\]
+		EncryptedCallDropped(T::BlockNumber, u32),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The call is not encrypted to the key of the current block.
+		InvalidTarget,
+		/// The key capsule is malformed.
+		InvalidCapsule,
+		/// The wrapped call is too short to hold a nonce and an authentication tag.
+		CiphertextTooShort,
+		/// The wrapped call is longer than `MaxCallLen`.
+		CiphertextTooLong,
+		/// The weight bound is above `MaxPendingWeight`.
+		WeightBoundTooHigh,
+		/// The block has reached `MaxPending` or `MaxPendingWeight`.
+		TooManyPending,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Submit a call sealed to the key of the current block, which is dispatched with the
+		/// origin of the submitter in the next block. The declared weight bound is charged with
+		/// this call, the wrapped call is dropped if it is heavier.
+		#[pallet::weight(
+			T::MempoolWeight::submit_encrypted(ciphertext.len() as u32).saturating_add(*weight_bound)
+		)]
+		pub fn submit_encrypted(
+			origin: OriginFor<T>,
+			capsule: KeyCapsule,
+			ciphertext: Vec<u8>,
+			weight_bound: Weight,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::check_wrapper(&capsule, ciphertext.len(), weight_bound)?;
+			let ciphertext: BoundedVec<u8, T::MaxCallLen> =
+				ciphertext.try_into().map_err(|_| Error::<T>::CiphertextTooLong)?;
+
+			let now = frame_system::Pallet::<T>::block_number();
+			let index = Pending::<T>::try_mutate(now, |pending| {
+				let reserved = pending
+					.iter()
+					.fold(0 as Weight, |total, call| total.saturating_add(call.weight_bound));
+				ensure!(
+					reserved.saturating_add(weight_bound) <= T::MaxPendingWeight::get(),
+					Error::<T>::TooManyPending
+				);
+				let wrapper =
+					EncryptedCall { submitter: who.clone(), weight_bound, capsule, ciphertext };
+				pending.try_push(wrapper).map_err(|_| Error::<T>::TooManyPending)?;
+				Ok::<_, Error<T>>(pending.len() as u32 - 1)
+			})?;
+			Self::deposit_event(Event::EncryptedCallSubmitted(who, now, index));
+			Ok(())
+		}
+
+		/// Decrypt the calls wrapped in the parent block with its released key and dispatch them
+		/// in order, this is an inherent provided by the block author after the released key is
+		/// stored.
+		#[pallet::weight((
+			T::MempoolWeight::execute_encrypted(T::MaxPending::get())
+				.saturating_add(T::MaxPendingWeight::get()),
+			DispatchClass::Mandatory
+		))]
+		pub fn execute_encrypted(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+			let release = frame_system::Pallet::<T>::block_number().saturating_sub(One::one());
+			let pending = Pending::<T>::take(release);
+			let mut weight = T::MempoolWeight::execute_encrypted(pending.len() as u32);
+			// the wrapped calls are dropped if the released key is not stored.
+			let key = T::ReleasedKeys::released_key(release);
+			for (index, wrapper) in pending.into_iter().enumerate() {
+				let index = index as u32;
+				let call = match key.as_ref().and_then(|key| Self::open(key, &wrapper)) {
+					Some(call) => call,
+					None => {
+						Self::deposit_event(Event::EncryptedCallDropped(release, index));
+						continue
+					},
+				};
+				let info = call.get_dispatch_info();
+				let origin = frame_system::RawOrigin::Signed(wrapper.submitter).into();
+				let result = call.dispatch(origin);
+				weight = weight.saturating_add(extract_actual_weight(&result, &info));
+				Self::deposit_event(Event::EncryptedCallDispatched(
+					release,
+					index,
+					result.map(|_| ()).map_err(|e| e.error),
+				));
+			}
+			Ok(Some(weight).into())
+		}
+	}
+
+	#[pallet::inherent]
+	impl<T: Config> ProvideInherent for Pallet<T> {
+		type Call = Call<T>;
+		type Error = MakeFatalError<()>;
+		const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+		fn create_inherent(_: &InherentData) -> Option<Self::Call> {
+			let release = frame_system::Pallet::<T>::block_number().saturating_sub(One::one());
+			let pending = Pending::<T>::decode_len(release).unwrap_or(0) > 0;
+			pending.then(|| Call::execute_encrypted {})
+		}
+
+		fn is_inherent_required(_: &InherentData) -> Result<Option<Self::Error>, Self::Error> {
+			// the inherents are checked on the state of the parent block, so the wrapped calls
+			// of the parent are pending at the current block number.
+			let release = frame_system::Pallet::<T>::block_number();
+			let required = Pending::<T>::decode_len(release).unwrap_or(0) > 0;
+			Ok(required.then(|| ().into()))
+		}
+
+		fn is_inherent(call: &Self::Call) -> bool {
+			matches!(call, Call::execute_encrypted { .. })
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Check a wrapped call targets the key of the current block and fits the limits.
+	pub fn check_wrapper(
+		capsule: &KeyCapsule,
+		len: usize,
+		weight_bound: Weight,
+	) -> Result<(), Error<T>> {
+		if T::BlockNumber::from(capsule.target) != frame_system::Pallet::<T>::block_number() {
+			return Err(Error::<T>::InvalidTarget)
+		}
+		if !capsule.has_valid_bit_length() || !capsule.is_reduced() {
+			return Err(Error::<T>::InvalidCapsule)
+		}
+		if len < NONCE_LEN + TAG_LEN {
+			return Err(Error::<T>::CiphertextTooShort)
+		}
+		if len > T::MaxCallLen::get() as usize {
+			return Err(Error::<T>::CiphertextTooLong)
+		}
+		if weight_bound > T::MaxPendingWeight::get() {
+			return Err(Error::<T>::WeightBoundTooHigh)
+		}
+		Ok(())
+	}
+
+	/// Decrypt a wrapped call with the released key, returns `None` if it can not be opened, it
+	/// is not a valid call or it exceeds its weight bound.
+	fn open(key: &RawReleasedKey, wrapper: &EncryptedCallOf<T>) -> Option<<T as Config>::Call> {
+		let secret = elgamal::decrypt(key, &wrapper.capsule)?;
+		let encoded = envelope::open(&wrapper.capsule, secret, &wrapper.ciphertext)?;
+		<T as Config>::Call::decode_all_with_depth_limit(MAX_CALL_DEPTH, &mut &encoded[..])
+			.ok()
+			.filter(|call| call.get_dispatch_info().weight <= wrapper.weight_bound)
+	}
+}
+
+/// Check the wrapped calls in the transaction pool, which only lives until the block whose key
+/// the call is encrypted to.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckEncryptedCall<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckEncryptedCall<T> {
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config + Send + Sync> Default for CheckEncryptedCall<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Config + Send + Sync> sp_std::fmt::Debug for CheckEncryptedCall<T> {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckEncryptedCall")
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckEncryptedCall<T>
+where
+	<T as frame_system::Config>::Call: IsSubType<Call<T>>,
+{
+	const IDENTIFIER: &'static str = "CheckEncryptedCall";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::Call;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		self.validate(who, call, info, len).map(|_| ())
+	}
+
+	fn validate(
+		&self,
+		_who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfo,
+		_len: usize,
+	) -> TransactionValidity {
+		let (capsule, len, weight_bound) = match call.is_sub_type() {
+			Some(Call::submit_encrypted { capsule, ciphertext, weight_bound }) =>
+				(capsule, ciphertext.len(), *weight_bound),
+			_ => return Ok(ValidTransaction::default()),
+		};
+		Pallet::<T>::check_wrapper(capsule, len, weight_bound).map_err(|error| match error {
+			Error::<T>::InvalidTarget
+				if T::BlockNumber::from(capsule.target) >
+					frame_system::Pallet::<T>::block_number() =>
+				InvalidTransaction::Future,
+			Error::<T>::InvalidTarget => InvalidTransaction::Stale,
+			Error::<T>::WeightBoundTooHigh => InvalidTransaction::ExhaustsResources,
+			_ => InvalidTransaction::Call,
+		})?;
+		// the key of the block is released once it is mined, so the call must not wait longer.
+		Ok(ValidTransaction { longevity: 1, ..Default::default() })
+	}
+}
@@ -0,0 +1,214 @@
+use crate::{mock::*, *};
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::{DispatchResult, DispatchResultWithPostInfo},
+	error::BadOrigin,
+};
+use sp_core::U256;
+
+/// A key over a 48 bit safe prime, which stands for the key released by the including block.
+fn released_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(98765432109u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+/// Seal a call to the key of the including block as `trex-client` does.
+fn seal(target: u32, payload: &[u8]) -> (KeyCapsule, Vec<u8>) {
+	let secret = U256::from(424242u64);
+	let capsule = elgamal::encrypt(&released_key(), target, secret, U256::from(987654u64));
+	let sealed = envelope::seal(&capsule, secret, [7u8; NONCE_LEN], payload);
+	(capsule, sealed)
+}
+
+fn transfer(dest: u64, value: u64) -> Call {
+	Call::Balances(pallet_balances::Call::transfer { dest, value })
+}
+
+fn submit(who: u64, payload: &[u8], weight_bound: Weight) -> DispatchResult {
+	let now = System::block_number() as u32;
+	let (capsule, sealed) = seal(now, payload);
+	Mempool::submit_encrypted(Origin::signed(who), capsule, sealed, weight_bound)
+}
+
+/// Execute the wrapped calls of the parent block once its released key is stored.
+fn execute(now: u64, key: RawReleasedKey) -> DispatchResultWithPostInfo {
+	System::set_block_number(now);
+	MockReleasedKeys::set(now - 1, key);
+	Mempool::execute_encrypted(Origin::none())
+}
+
+const BOUND: Weight = 500_000_000;
+
+#[test]
+fn submit_encrypted_checks_the_wrapper() {
+	new_test_ext().execute_with(|| {
+		let (capsule, sealed) = seal(1, &transfer(2, 50).encode());
+		let submit_sealed = |capsule: KeyCapsule, sealed: Vec<u8>, bound: Weight| {
+			Mempool::submit_encrypted(Origin::signed(1), capsule, sealed, bound)
+		};
+
+		for target in [0, 2] {
+			assert_noop!(
+				submit_sealed(seal(target, &[]).0, sealed.clone(), BOUND),
+				Error::<Test>::InvalidTarget
+			);
+		}
+		let malformed = KeyCapsule { c1: capsule.p, ..capsule.clone() };
+		assert_noop!(
+			submit_sealed(malformed, sealed.clone(), BOUND),
+			Error::<Test>::InvalidCapsule
+		);
+		assert_noop!(
+			submit_sealed(capsule.clone(), vec![0; 27], BOUND),
+			Error::<Test>::CiphertextTooShort
+		);
+		assert_noop!(
+			submit_sealed(capsule.clone(), vec![0; 129], BOUND),
+			Error::<Test>::CiphertextTooLong
+		);
+		assert_noop!(
+			submit_sealed(capsule.clone(), sealed.clone(), MaxPendingWeight::get() + 1),
+			Error::<Test>::WeightBoundTooHigh
+		);
+
+		assert_ok!(submit_sealed(capsule.clone(), sealed.clone(), BOUND));
+		assert_eq!(
+			Mempool::pending(1).into_inner(),
+			vec![EncryptedCall {
+				submitter: 1,
+				weight_bound: BOUND,
+				capsule,
+				ciphertext: sealed.try_into().unwrap(),
+			}]
+		);
+		System::assert_last_event(mock::Event::Mempool(crate::Event::EncryptedCallSubmitted(
+			1, 1, 0,
+		)));
+	});
+}
+
+#[test]
+fn submit_encrypted_limits_the_block() {
+	new_test_ext().execute_with(|| {
+		let call = transfer(2, 50).encode();
+		assert_ok!(submit(1, &call, BOUND));
+		// the weight bounds of a block are limited by `MaxPendingWeight`.
+		assert_noop!(submit(1, &call, BOUND + 1), Error::<Test>::TooManyPending);
+		assert_ok!(submit(1, &call, BOUND / 2));
+		assert_ok!(submit(1, &call, BOUND / 2));
+		// the number of wrappers in a block is limited by `MaxPending`.
+		assert_noop!(submit(1, &call, 0), Error::<Test>::TooManyPending);
+
+		// the limits apply to each block.
+		System::set_block_number(2);
+		assert_ok!(submit(1, &call, BOUND));
+		assert_eq!(Mempool::pending(1).len(), 3);
+		assert_eq!(Mempool::pending(2).len(), 1);
+	});
+}
+
+#[test]
+fn encrypted_calls_are_dispatched_in_order() {
+	new_test_ext().execute_with(|| {
+		// the second call spends the funds transferred by the first one.
+		assert_ok!(submit(1, &transfer(2, 500).encode(), BOUND));
+		assert_ok!(submit(2, &transfer(3, 1_400).encode(), BOUND));
+
+		assert_noop!(Mempool::execute_encrypted(Origin::signed(1)), BadOrigin);
+		assert_ok!(execute(2, released_key()));
+		System::assert_has_event(mock::Event::Mempool(crate::Event::EncryptedCallDispatched(
+			1,
+			0,
+			Ok(()),
+		)));
+		System::assert_last_event(mock::Event::Mempool(crate::Event::EncryptedCallDispatched(
+			1,
+			1,
+			Ok(()),
+		)));
+		assert_eq!(Balances::free_balance(1), 500);
+		assert_eq!(Balances::free_balance(2), 100);
+		assert_eq!(Balances::free_balance(3), 2_400);
+		assert!(Mempool::pending(1).is_empty());
+	});
+}
+
+#[test]
+fn invalid_calls_are_dropped() {
+	new_test_ext().execute_with(|| {
+		let call = transfer(2, 50).encode();
+		assert_ok!(submit(1, &call, 0));
+		assert_ok!(submit(1, b"not a call", BOUND));
+		let (capsule, mut sealed) = seal(1, &call);
+		sealed[NONCE_LEN] ^= 1;
+		assert_ok!(Mempool::submit_encrypted(Origin::signed(1), capsule, sealed, BOUND));
+
+		assert_ok!(execute(2, released_key()));
+		for index in 0..3 {
+			System::assert_has_event(mock::Event::Mempool(crate::Event::EncryptedCallDropped(
+				1, index,
+			)));
+		}
+		assert_eq!(Balances::free_balance(2), 1_000);
+		assert!(Mempool::pending(1).is_empty());
+
+		// the calls are dropped without the released key.
+		assert_ok!(submit(1, &call, BOUND));
+		System::set_block_number(3);
+		assert_ok!(Mempool::execute_encrypted(Origin::none()));
+		System::assert_last_event(mock::Event::Mempool(crate::Event::EncryptedCallDropped(2, 0)));
+		assert_eq!(Balances::free_balance(2), 1_000);
+	});
+}
+
+#[test]
+fn inherent_is_required_for_pending_calls() {
+	new_test_ext().execute_with(|| {
+		let data = InherentData::new();
+		System::set_block_number(2);
+		assert_eq!(Mempool::create_inherent(&data), None);
+		assert!(matches!(Mempool::is_inherent_required(&data), Ok(None)));
+
+		System::set_block_number(1);
+		assert_ok!(submit(1, &transfer(2, 50).encode(), BOUND));
+		// the inherents of the next block are checked on the state of this block.
+		assert!(matches!(Mempool::is_inherent_required(&data), Ok(Some(_))));
+
+		System::set_block_number(2);
+		let call = Mempool::create_inherent(&data).unwrap();
+		assert_eq!(call, crate::Call::execute_encrypted {});
+		assert!(Mempool::is_inherent(&call));
+	});
+}
+
+#[test]
+fn extension_checks_the_wrapper_in_the_pool() {
+	new_test_ext().execute_with(|| {
+		let check = |target: u32, bound: Weight| {
+			let (capsule, ciphertext) = seal(target, &transfer(2, 50).encode());
+			let call = Call::Mempool(crate::Call::submit_encrypted {
+				capsule,
+				ciphertext,
+				weight_bound: bound,
+			});
+			CheckEncryptedCall::<Test>::new().validate(&1, &call, &Default::default(), 0)
+		};
+
+		System::set_block_number(2);
+		assert_eq!(check(2, BOUND).map(|valid| valid.longevity), Ok(1));
+		assert_eq!(check(1, BOUND), Err(InvalidTransaction::Stale.into()));
+		assert_eq!(check(3, BOUND), Err(InvalidTransaction::Future.into()));
+		assert_eq!(
+			check(2, MaxPendingWeight::get() + 1),
+			Err(InvalidTransaction::ExhaustsResources.into())
+		);
+		// other calls are not checked.
+		let call = transfer(2, 50);
+		assert_eq!(
+			CheckEncryptedCall::<Test>::new().validate(&1, &call, &Default::default(), 0),
+			Ok(ValidTransaction::default())
+		);
+	});
+}
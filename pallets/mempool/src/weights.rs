@@ -0,0 +1,59 @@
+//! Weights for pallet_trex_mempool
+//!
+//! Guessed from the storage accesses and the cost of opening one wrapped call of the maximum length,
+//! to be replaced by the output of the benchmarks of `benchmarking.rs`. The weights of the
+//! dispatched calls are added to `execute_encrypted` by the pallet.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_trex_mempool.
+pub trait MempoolWeight {
+    fn submit_encrypted(l: u32, ) -> Weight;
+    fn execute_encrypted(n: u32, ) -> Weight;
+}
+
+/// Weights for pallet_trex_mempool using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> MempoolWeight for SubstrateWeight<T> {
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXMempool Pending (r:1 w:1)
+    fn submit_encrypted(l: u32, ) -> Weight {
+        (30_000_000 as Weight)
+            .saturating_add((2_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXMempool Pending (r:1 w:1)
+    fn execute_encrypted(n: u32, ) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((60_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+}
+
+// For backwards compatibility and tests
+impl MempoolWeight for () {
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXMempool Pending (r:1 w:1)
+    fn submit_encrypted(l: u32, ) -> Weight {
+        (30_000_000 as Weight)
+            .saturating_add((2_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add(RocksDbWeight::get().reads(2 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXMempool Pending (r:1 w:1)
+    fn execute_encrypted(n: u32, ) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((60_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(RocksDbWeight::get().reads(2 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+}
@@ -0,0 +1,34 @@
+//! Benchmarking setup for pallet-trex-randomness
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Beacon;
+use frame_benchmarking::benchmarks;
+use frame_support::traits::Hooks;
+use pallet_trex::elgamal;
+use sp_core::U256;
+
+/// A key over a 48 bit safe prime, the hashing of the key dominates the mixing.
+fn bench_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(98765432109u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+benchmarks! {
+	// The weight of the mixing in `on_finalize` is charged by `on_initialize`.
+	mix_released_key {
+		let now: T::BlockNumber = 2u32.into();
+		frame_system::Pallet::<T>::set_block_number(now);
+		T::ReleasedKeys::insert_released_key(1u32.into(), bench_key());
+	}: {
+		Beacon::<T>::on_finalize(now);
+	}
+	verify {
+		assert_eq!(ReleasedAt::<T>::get(), 1u32.into());
+	}
+
+	impl_benchmark_test_suite!(Beacon, crate::mock::new_test_ext(), crate::mock::Test);
+}
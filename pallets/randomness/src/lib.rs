@@ -0,0 +1,78 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Randomness beacon of TREX, built on the private keys released by the mined blocks.
+//!
+//! The private key of a block is the solution of its proof of work, so nobody knows it before the
+//! block is mined, and the seal releases it. The key released by the parent block is stored by
+//! `pallet_trex_keys`, and it is mixed into a hash chain when the block is finalized. The pallet
+//! implements `Randomness` over the hash chain, whose output is determinable since the parent block
+//! was mined.
+use frame_support::traits::Randomness;
+pub use pallet::*;
+pub use pallet_trex::{RawReleasedKey, ReleasedKeys};
+use sp_runtime::traits::{Hash, One, Saturating};
+pub mod migrations;
+pub mod weights;
+pub use weights::RandomnessWeight;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The keys released by the mined blocks.
+		type ReleasedKeys: ReleasedKeys<Self::BlockNumber>;
+
+		/// Weight information for extrinsics in this pallet.
+		type RandomnessWeight: RandomnessWeight;
+	}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	/// The head of the hash chain of the released keys.
+	#[pallet::storage]
+	#[pallet::getter(fn seed)]
+	pub type Seed<T: Config> = StorageValue<_, T::Hash, ValueQuery>;
+
+	/// Height of the block whose released key is the last one mixed into the seed.
+	#[pallet::storage]
+	#[pallet::getter(fn released_at)]
+	pub type ReleasedAt<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_: T::BlockNumber) -> Weight {
+			T::RandomnessWeight::mix_released_key()
+		}
+
+		/// Mix the key released by the parent block, which is stored by the inherents.
+		fn on_finalize(now: T::BlockNumber) {
+			let release = now.saturating_sub(One::one());
+			if let Some(key) = T::ReleasedKeys::released_key(release) {
+				Seed::<T>::mutate(|seed| *seed = T::Hashing::hash_of(&(*seed, key)));
+				ReleasedAt::<T>::put(release);
+			}
+		}
+	}
+}
+
+impl<T: Config> Randomness<T::Hash, T::BlockNumber> for Pallet<T> {
+	/// The random value of a subject mixed with the seed, which is determinable since the block
+	/// releasing the last key is mined.
+	fn random(subject: &[u8]) -> (T::Hash, T::BlockNumber) {
+		(T::Hashing::hash_of(&(subject, Seed::<T>::get())), ReleasedAt::<T>::get())
+	}
+}
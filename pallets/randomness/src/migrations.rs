@@ -0,0 +1,35 @@
+//! Storage migrations of the randomness beacon.
+
+use super::*;
+use frame_support::{
+	storage::{storage_prefix, unhashed},
+	traits::{Get, OnRuntimeUpgrade},
+	weights::Weight,
+};
+use sp_std::marker::PhantomData;
+
+pub mod v1 {
+	use super::*;
+
+	/// The pallet name of `pallet_randomness_collective_flip` in the runtime, which is replaced
+	/// by the beacon.
+	pub const COLLECTIVE_FLIP: &[u8] = b"RandomnessCollectiveFlip";
+
+	/// Runtime upgrade from the collective flip to the beacon, it is run by the `Executive` of the
+	/// runtime.
+	pub struct RemoveCollectiveFlip<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for RemoveCollectiveFlip<T> {
+		fn on_runtime_upgrade() -> Weight {
+			migrate::<T>()
+		}
+	}
+
+	/// Remove the storage left by `pallet_randomness_collective_flip`, its `RandomMaterial` and
+	/// its storage version. The removal is idempotent, so it is harmless once the storage is gone.
+	pub fn migrate<T: Config>() -> Weight {
+		unhashed::kill(&storage_prefix(COLLECTIVE_FLIP, b"RandomMaterial"));
+		unhashed::kill(&storage_prefix(COLLECTIVE_FLIP, b":__STORAGE_VERSION__:"));
+		T::DbWeight::get().writes(2)
+	}
+}
@@ -0,0 +1,94 @@
+use crate as pallet_trex_randomness;
+use frame_support::{parameter_types, traits::Everything};
+use frame_system as system;
+use pallet_trex::{RawReleasedKey, ReleasedKeys};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use std::{cell::RefCell, collections::BTreeMap};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Beacon: pallet_trex_randomness::{Pallet, Storage},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+thread_local! {
+	/// The keys stored by the inherents of the blocks, by the height of the released block.
+	pub static RELEASED_KEYS: RefCell<BTreeMap<u64, RawReleasedKey>> = RefCell::new(BTreeMap::new());
+}
+
+pub struct MockReleasedKeys;
+
+impl MockReleasedKeys {
+	pub fn set(height: u64, key: RawReleasedKey) {
+		RELEASED_KEYS.with(|keys| keys.borrow_mut().insert(height, key));
+	}
+}
+
+impl ReleasedKeys<u64> for MockReleasedKeys {
+	fn released_key(height: u64) -> Option<RawReleasedKey> {
+		RELEASED_KEYS.with(|keys| keys.borrow().get(&height).cloned())
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn insert_released_key(height: u64, key: RawReleasedKey) {
+		Self::set(height, key)
+	}
+}
+
+impl pallet_trex_randomness::Config for Test {
+	type ReleasedKeys = MockReleasedKeys;
+	type RandomnessWeight = ();
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,79 @@
+use crate::{mock::*, *};
+use frame_support::{
+	storage::{storage_prefix, unhashed},
+	traits::{Hooks, OnRuntimeUpgrade},
+};
+use pallet_trex::elgamal;
+use sp_core::{H256, U256};
+use sp_runtime::traits::BlakeTwo256;
+
+/// A key over a 48 bit safe prime with the private key `x`, which stands for a released key.
+fn released_key(x: u64) -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(x);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+/// Finalize a block with the key released by its parent.
+fn finalize(now: u64) {
+	System::set_block_number(now);
+	Beacon::on_finalize(now);
+}
+
+#[test]
+fn released_keys_are_chained_into_the_seed() {
+	new_test_ext().execute_with(|| {
+		// nothing is mixed until a key is released.
+		finalize(1);
+		assert_eq!(Beacon::seed(), H256::zero());
+		assert_eq!(Beacon::released_at(), 0);
+
+		MockReleasedKeys::set(1, released_key(98765432109));
+		finalize(2);
+		let seed = BlakeTwo256::hash_of(&(H256::zero(), released_key(98765432109)));
+		assert_eq!(Beacon::seed(), seed);
+		assert_eq!(Beacon::released_at(), 1);
+
+		MockReleasedKeys::set(2, released_key(12345));
+		finalize(3);
+		assert_eq!(Beacon::seed(), BlakeTwo256::hash_of(&(seed, released_key(12345))));
+		assert_eq!(Beacon::released_at(), 2);
+	});
+}
+
+#[test]
+fn random_depends_on_the_subject_and_the_seed() {
+	new_test_ext().execute_with(|| {
+		MockReleasedKeys::set(1, released_key(98765432109));
+		finalize(2);
+		let (random, known_since) = Beacon::random(b"subject");
+		assert_eq!(known_since, 1);
+		assert_ne!(Beacon::random(b"another subject").0, random);
+
+		MockReleasedKeys::set(2, released_key(12345));
+		finalize(3);
+		assert_eq!(
+			Beacon::random(b"subject"),
+			(BlakeTwo256::hash_of(&(b"subject", Beacon::seed())), 2)
+		);
+		assert_ne!(Beacon::random(b"subject").0, random);
+	});
+}
+
+#[test]
+fn migrate_removes_collective_flip_storage() {
+	new_test_ext().execute_with(|| {
+		let material = storage_prefix(b"RandomnessCollectiveFlip", b"RandomMaterial");
+		let version = storage_prefix(b"RandomnessCollectiveFlip", b":__STORAGE_VERSION__:");
+		unhashed::put(&material, &vec![H256::repeat_byte(1); 81]);
+		unhashed::put(&version, &0u16);
+
+		migrations::v1::RemoveCollectiveFlip::<Test>::on_runtime_upgrade();
+		assert!(!unhashed::exists(&material));
+		assert!(!unhashed::exists(&version));
+		// the removal is idempotent.
+		migrations::v1::migrate::<Test>();
+		assert!(!unhashed::exists(&material));
+	});
+}
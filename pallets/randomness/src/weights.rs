@@ -0,0 +1,39 @@
+//! Weights for pallet_trex_randomness
+//!
+//! An approximation of the storage accesses of the hooks and a single hash of the released key, not a
+//! measurement; `mix_released_key` in `benchmarking.rs` is the benchmark to regenerate it from.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_trex_randomness.
+pub trait RandomnessWeight {
+    fn mix_released_key() -> Weight;
+}
+
+/// Weights for pallet_trex_randomness using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> RandomnessWeight for SubstrateWeight<T> {
+    // Storage: TREXKeys Released (r:1 w:0)
+    // Storage: TREXRandomness Seed (r:1 w:1), ReleasedAt (r:0 w:1)
+    fn mix_released_key() -> Weight {
+        (25_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(3 as Weight))
+            .saturating_add(T::DbWeight::get().writes(2 as Weight))
+    }
+}
+
+// For backwards compatibility and tests
+impl RandomnessWeight for () {
+    // Storage: TREXKeys Released (r:1 w:0)
+    // Storage: TREXRandomness Seed (r:1 w:1), ReleasedAt (r:0 w:1)
+    fn mix_released_key() -> Weight {
+        (25_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(3 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(2 as Weight))
+    }
+}
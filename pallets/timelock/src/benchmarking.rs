@@ -0,0 +1,82 @@
+//! Benchmarking setup for pallet-trex-timelock
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Timelock;
+use frame_benchmarking::{benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+use sp_runtime::traits::Bounded;
+use sp_std::vec;
+
+/// The release block of the benchmarked calls.
+const RELEASE: u32 = 10;
+
+/// A key over a 48 bit safe prime, the cost of the arithmetic is dominated by the big integers.
+fn bench_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(98765432109u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+/// Seal a payload to the key of the release block, returns the capsule, the sealed call of `l`
+/// bytes and the secret of the capsule.
+fn sealed(l: u32) -> (KeyCapsule, Vec<u8>, U256) {
+	let secret = U256::from(424242u64);
+	let capsule = elgamal::encrypt(&bench_key(), RELEASE, secret, U256::from(987654u64));
+	let payload = vec![0xff; l as usize - NONCE_LEN - TAG_LEN];
+	let ciphertext = envelope::seal(&capsule, secret, [7u8; NONCE_LEN], &payload);
+	(capsule, ciphertext, secret)
+}
+
+fn funded<T: Config>(who: &T::AccountId) {
+	T::Currency::make_free_balance_be(who, BalanceOf::<T>::max_value() / 2u32.into());
+}
+
+/// Schedule a sealed call of `l` bytes released at `RELEASE`, returns its secret.
+fn scheduled<T: Config>(who: &T::AccountId, l: u32) -> U256 {
+	frame_system::Pallet::<T>::set_block_number(1u32.into());
+	let (capsule, ciphertext, secret) = sealed(l);
+	Timelock::<T>::schedule_sealed(RawOrigin::Signed(who.clone()).into(), capsule, ciphertext, 0)
+		.expect("the submitter is funded");
+	secret
+}
+
+benchmarks! {
+	schedule_sealed {
+		let l in (NONCE_LEN + TAG_LEN) as u32 .. T::MaxCallLen::get();
+		let caller: T::AccountId = whitelisted_caller();
+		funded::<T>(&caller);
+		frame_system::Pallet::<T>::set_block_number(1u32.into());
+		let (capsule, ciphertext, _) = sealed(l);
+	}: _(RawOrigin::Signed(caller), capsule, ciphertext, T::MaxCallWeight::get())
+	verify {
+		assert!(Tasks::<T>::contains_key(0));
+	}
+
+	cancel {
+		let caller: T::AccountId = whitelisted_caller();
+		funded::<T>(&caller);
+		scheduled::<T>(&caller, T::MaxCallLen::get());
+	}: _(RawOrigin::Signed(caller), 0)
+	verify {
+		assert!(!Tasks::<T>::contains_key(0));
+	}
+
+	// The sealed payload is not a call, so only the opening is measured, the weight of a
+	// dispatched call is added by the pallet.
+	dispatch_sealed {
+		let l in (NONCE_LEN + TAG_LEN) as u32 .. T::MaxCallLen::get();
+		let caller: T::AccountId = whitelisted_caller();
+		funded::<T>(&caller);
+		let secret = scheduled::<T>(&caller, l);
+		frame_system::Pallet::<T>::set_block_number((RELEASE + 1).into());
+		T::ReleasedKeys::insert_released_key(RELEASE.into(), bench_key());
+	}: _(RawOrigin::Signed(caller), 0, secret, 0)
+	verify {
+		assert!(!Tasks::<T>::contains_key(0));
+	}
+
+	impl_benchmark_test_suite!(Timelock, crate::mock::new_test_ext(), crate::mock::Test);
+}
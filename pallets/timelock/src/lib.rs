@@ -0,0 +1,290 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Runtime calls sealed until a block, built on the timed release encryption of TREX.
+//!
+//! A sealed call is a SCALE encoded `Call` in a timed release envelope of `trex-client`: a key
+//! capsule of a random secret encrypted to the public key mined at the target block, followed by
+//! a nonce and the call encrypted with ChaCha20-Poly1305 under the key derived from the secret.
+//! The sealed call is stored with a weight bound and a deposit until the target block is mined.
+//! Once its private key is released and stored on chain, anyone can submit the secret of the
+//! capsule, which is checked against the capsule with the stored key and opens the call, and the
+//! call is dispatched with the origin of the account which scheduled it.
+use codec::DecodeLimit;
+use frame_support::{
+	dispatch::{Dispatchable, GetDispatchInfo, PostDispatchInfo},
+	traits::{Currency, Get, ReservableCurrency},
+	weights::{extract_actual_weight, Weight},
+};
+pub use pallet::*;
+use pallet_trex::envelope::{NONCE_LEN, TAG_LEN};
+pub use pallet_trex::{elgamal, envelope, KeyCapsule, RawReleasedKey, ReleasedKeys};
+use sp_core::U256;
+use sp_runtime::traits::Saturating;
+use sp_std::vec::Vec;
+pub mod weights;
+pub use weights::TimelockWeight;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Maximum depth of the nested calls in a sealed call, the same as the extrinsics.
+const MAX_CALL_DEPTH: u32 = 256;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	/// Identifier of a sealed call.
+	pub type TaskId = u32;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The runtime call which can be sealed.
+		type Call: Parameter
+			+ Dispatchable<Origin = Self::Origin, PostInfo = PostDispatchInfo>
+			+ GetDispatchInfo
+			+ IsType<<Self as frame_system::Config>::Call>;
+
+		/// The currency to reserve the deposits of sealed calls.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// The deposit reserved for each sealed call.
+		#[pallet::constant]
+		type DepositPerItem: Get<BalanceOf<Self>>;
+
+		/// The deposit reserved for each byte of a sealed call.
+		#[pallet::constant]
+		type DepositPerByte: Get<BalanceOf<Self>>;
+
+		/// Maximum length of a sealed call in bytes, including its nonce.
+		#[pallet::constant]
+		type MaxCallLen: Get<u32>;
+
+		/// Maximum weight bound of a sealed call.
+		#[pallet::constant]
+		type MaxCallWeight: Get<Weight>;
+
+		/// The keys released by the mined blocks, which open the sealed calls.
+		type ReleasedKeys: ReleasedKeys<Self::BlockNumber>;
+
+		/// Weight information for extrinsics in this pallet.
+		type TimelockWeight: TimelockWeight;
+	}
+
+	/// Type alias for currency balance.
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	/// A sealed call waiting for the release of its key.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct TaskInfo<AccountId, Balance, BlockNumber, Ciphertext> {
+		/// Account which scheduled the call, which is the origin of the dispatch.
+		pub submitter: AccountId,
+		/// Deposit reserved from the submitter until the call is dispatched or cancelled.
+		pub deposit: Balance,
+		/// Height of the block whose key releases the call.
+		pub release: BlockNumber,
+		/// Maximum weight of the call, a heavier call is discarded.
+		pub weight_bound: Weight,
+		/// The secret of the symmetric key encrypted to the key of the release height.
+		pub capsule: KeyCapsule,
+		/// The nonce followed by the encrypted call.
+		pub ciphertext: Ciphertext,
+	}
+
+	pub type TaskInfoOf<T> = TaskInfo<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+		BoundedVec<u8, <T as Config>::MaxCallLen>,
+	>;
+
+	/// The identifier of the next sealed call.
+	#[pallet::storage]
+	#[pallet::getter(fn next_task_id)]
+	pub type NextTaskId<T> = StorageValue<_, TaskId, ValueQuery>;
+
+	/// The sealed calls which are not dispatched yet.
+	#[pallet::storage]
+	#[pallet::getter(fn tasks)]
+	pub type Tasks<T: Config> = StorageMap<_, Twox64Concat, TaskId, TaskInfoOf<T>>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A sealed call is scheduled. \[task_id, submitter, release\]
+		CallScheduled(TaskId, T::AccountId, T::BlockNumber),
+		/// A sealed call is cancelled by its submitter. \[task_id\]
+		CallCancelled(TaskId),
+		/// A sealed call is opened and dispatched. \[task_id, result\]
+		CallDispatched(TaskId, DispatchResult),
+		/// A sealed call is opened but it is not a valid call, so it is discarded. \[task_id\]
+		CallUndecodable(TaskId),
+		/// A sealed call is opened but it exceeds its weight bound, so it is discarded.
+		/// \[task_id, weight\]
+		CallOverweight(TaskId, Weight),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The target of the key capsule is not in the future.
+		TargetNotInFuture,
+		/// The key capsule is malformed.
+		InvalidCapsule,
+		/// The sealed call is too short to hold a nonce and an authentication tag.
+		CiphertextTooShort,
+		/// The sealed call is longer than `MaxCallLen`.
+		CiphertextTooLong,
+		/// The weight bound is above `MaxCallWeight`.
+		WeightBoundTooHigh,
+		/// The submitter can not afford the deposit.
+		InsufficientDeposit,
+		/// No sealed call is found with the identifier.
+		TaskNotFound,
+		/// Only the submitter can cancel a sealed call.
+		NotSubmitter,
+		/// The key of the release height is not released yet.
+		KeyNotReleased,
+		/// The maximum weight of the dispatch is below the weight bound of the call.
+		MaxWeightTooLow,
+		/// The secret is not the plaintext of the capsule, or it does not open the sealed call.
+		InvalidPlaintext,
+		/// Next task id overflows.
+		TaskIdOverflow,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Schedule a sealed call released by the target block of its key capsule. The call must
+		/// weigh at most `weight_bound` when it is dispatched, and a deposit is reserved for the
+		/// sealed call until then.
+		#[pallet::weight(T::TimelockWeight::schedule_sealed(ciphertext.len() as u32))]
+		pub fn schedule_sealed(
+			origin: OriginFor<T>,
+			capsule: KeyCapsule,
+			ciphertext: Vec<u8>,
+			weight_bound: Weight,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let release = T::BlockNumber::from(capsule.target);
+			ensure!(
+				release > frame_system::Pallet::<T>::block_number(),
+				Error::<T>::TargetNotInFuture
+			);
+			ensure!(
+				capsule.has_valid_bit_length() && capsule.is_reduced(),
+				Error::<T>::InvalidCapsule
+			);
+			ensure!(ciphertext.len() >= NONCE_LEN + TAG_LEN, Error::<T>::CiphertextTooShort);
+			ensure!(weight_bound <= T::MaxCallWeight::get(), Error::<T>::WeightBoundTooHigh);
+			let deposit = Self::deposit_of(ciphertext.len());
+			let ciphertext: BoundedVec<u8, T::MaxCallLen> =
+				ciphertext.try_into().map_err(|_| Error::<T>::CiphertextTooLong)?;
+
+			let id = NextTaskId::<T>::get();
+			let next = id.checked_add(1).ok_or(Error::<T>::TaskIdOverflow)?;
+			T::Currency::reserve(&who, deposit).map_err(|_| Error::<T>::InsufficientDeposit)?;
+			NextTaskId::<T>::put(next);
+			let info = TaskInfo {
+				submitter: who.clone(),
+				deposit,
+				release,
+				weight_bound,
+				capsule,
+				ciphertext,
+			};
+			Tasks::<T>::insert(id, info);
+			Self::deposit_event(Event::CallScheduled(id, who, release));
+			Ok(())
+		}
+
+		/// Cancel a sealed call which is not dispatched yet, and release its deposit.
+		#[pallet::weight(T::TimelockWeight::cancel())]
+		pub fn cancel(origin: OriginFor<T>, task_id: TaskId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let task = Tasks::<T>::get(task_id).ok_or(Error::<T>::TaskNotFound)?;
+			ensure!(task.submitter == who, Error::<T>::NotSubmitter);
+
+			Tasks::<T>::remove(task_id);
+			T::Currency::unreserve(&who, task.deposit);
+			Self::deposit_event(Event::CallCancelled(task_id));
+			Ok(())
+		}
+
+		/// Open a sealed call with the secret of its key capsule and dispatch it with the origin
+		/// of its submitter, which can be dispatched by anyone once the key of the release block
+		/// is stored.
+		///
+		/// The secret must be the plaintext of the capsule under the stored key and open the
+		/// sealed call. `max_weight` must cover the weight bound of the call, only the weight
+		/// used by the call is charged. A call which can not be decoded or exceeds its weight
+		/// bound is discarded. The deposit is released in any case.
+		#[pallet::weight(
+			T::TimelockWeight::dispatch_sealed(T::MaxCallLen::get()).saturating_add(*max_weight)
+		)]
+		pub fn dispatch_sealed(
+			origin: OriginFor<T>,
+			task_id: TaskId,
+			secret: U256,
+			max_weight: Weight,
+		) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+			let task = Tasks::<T>::get(task_id).ok_or(Error::<T>::TaskNotFound)?;
+			let key =
+				T::ReleasedKeys::released_key(task.release).ok_or(Error::<T>::KeyNotReleased)?;
+			ensure!(max_weight >= task.weight_bound, Error::<T>::MaxWeightTooLow);
+			ensure!(elgamal::verify(&key, &task.capsule, secret), Error::<T>::InvalidPlaintext);
+			let encoded = envelope::open(&task.capsule, secret, &task.ciphertext)
+				.ok_or(Error::<T>::InvalidPlaintext)?;
+
+			Tasks::<T>::remove(task_id);
+			T::Currency::unreserve(&task.submitter, task.deposit);
+			let weight = T::TimelockWeight::dispatch_sealed(task.ciphertext.len() as u32);
+			let call = match <T as Config>::Call::decode_all_with_depth_limit(
+				MAX_CALL_DEPTH,
+				&mut &encoded[..],
+			) {
+				Ok(call) => call,
+				Err(_) => {
+					Self::deposit_event(Event::CallUndecodable(task_id));
+					return Ok(Some(weight).into())
+				},
+			};
+			let info = call.get_dispatch_info();
+			if info.weight > task.weight_bound {
+				Self::deposit_event(Event::CallOverweight(task_id, info.weight));
+				return Ok(Some(weight).into())
+			}
+
+			let result = call.dispatch(frame_system::RawOrigin::Signed(task.submitter).into());
+			let weight = weight.saturating_add(extract_actual_weight(&result, &info));
+			Self::deposit_event(Event::CallDispatched(
+				task_id,
+				result.map(|_| ()).map_err(|e| e.error),
+			));
+			Ok(Some(weight).into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The deposit to store a sealed call of the given length.
+	pub fn deposit_of(len: usize) -> BalanceOf<T> {
+		let bytes: BalanceOf<T> = (len as u32).into();
+		T::DepositPerItem::get().saturating_add(T::DepositPerByte::get().saturating_mul(bytes))
+	}
+}
@@ -0,0 +1,182 @@
+use crate::{mock::*, *};
+use codec::Encode;
+use frame_support::{assert_noop, assert_ok};
+
+/// A key over a 48 bit safe prime, which stands for the key released by the target block.
+fn released_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(98765432109u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+/// Seal a payload to the key of the target block as `trex-client` does, returns the capsule, the
+/// sealed payload and the secret of the capsule.
+fn seal(target: u32, payload: &[u8]) -> (KeyCapsule, Vec<u8>, U256) {
+	let secret = U256::from(424242u64);
+	let capsule = elgamal::encrypt(&released_key(), target, secret, U256::from(987654u64));
+	let sealed = envelope::seal(&capsule, secret, [7u8; NONCE_LEN], payload);
+	(capsule, sealed, secret)
+}
+
+/// Mine the block after the release block, which stores the key released by the release block.
+fn mine_after_release(release: u64) {
+	System::set_block_number(release + 1);
+	MockReleasedKeys::set(release, released_key());
+}
+
+fn transfer(dest: u64, value: u64) -> Call {
+	Call::Balances(pallet_balances::Call::transfer { dest, value })
+}
+
+#[test]
+fn schedule_sealed_works() {
+	new_test_ext().execute_with(|| {
+		let (capsule, sealed, _) = seal(5, &transfer(2, 50).encode());
+		let bound = MaxCallWeight::get();
+
+		assert_noop!(
+			Timelock::schedule_sealed(Origin::signed(1), seal(1, &[]).0, sealed.clone(), bound),
+			Error::<Test>::TargetNotInFuture
+		);
+		let malformed = KeyCapsule { c1: capsule.p, ..capsule.clone() };
+		assert_noop!(
+			Timelock::schedule_sealed(Origin::signed(1), malformed, sealed.clone(), bound),
+			Error::<Test>::InvalidCapsule
+		);
+		assert_noop!(
+			Timelock::schedule_sealed(Origin::signed(1), capsule.clone(), vec![0; 27], bound),
+			Error::<Test>::CiphertextTooShort
+		);
+		assert_noop!(
+			Timelock::schedule_sealed(Origin::signed(1), capsule.clone(), vec![0; 129], bound),
+			Error::<Test>::CiphertextTooLong
+		);
+		assert_noop!(
+			Timelock::schedule_sealed(
+				Origin::signed(1),
+				capsule.clone(),
+				sealed.clone(),
+				bound + 1
+			),
+			Error::<Test>::WeightBoundTooHigh
+		);
+		assert_noop!(
+			Timelock::schedule_sealed(Origin::signed(3), capsule.clone(), sealed.clone(), bound),
+			Error::<Test>::InsufficientDeposit
+		);
+
+		assert_ok!(Timelock::schedule_sealed(
+			Origin::signed(1),
+			capsule.clone(),
+			sealed.clone(),
+			bound
+		));
+		let deposit = Timelock::deposit_of(sealed.len());
+		assert_eq!(
+			Timelock::tasks(0),
+			Some(TaskInfo {
+				submitter: 1,
+				deposit,
+				release: 5,
+				weight_bound: bound,
+				capsule,
+				ciphertext: sealed.try_into().unwrap(),
+			})
+		);
+		assert_eq!(Timelock::next_task_id(), 1);
+		assert_eq!(Balances::reserved_balance(1), deposit);
+		System::assert_last_event(mock::Event::Timelock(crate::Event::CallScheduled(0, 1, 5)));
+	});
+}
+
+#[test]
+fn sealed_call_is_dispatched_with_the_submitter_origin() {
+	new_test_ext().execute_with(|| {
+		let (capsule, sealed, secret) = seal(5, &transfer(2, 50).encode());
+		let bound = MaxCallWeight::get();
+		assert_ok!(Timelock::schedule_sealed(Origin::signed(1), capsule, sealed, bound));
+
+		// anyone can open the call once the key of the release block is stored.
+		mine_after_release(5);
+		assert_ok!(Timelock::dispatch_sealed(Origin::signed(3), 0, secret, bound));
+		System::assert_last_event(mock::Event::Timelock(crate::Event::CallDispatched(0, Ok(()))));
+		assert_eq!(Balances::free_balance(1), 950);
+		assert_eq!(Balances::free_balance(2), 1_050);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Timelock::tasks(0), None);
+		assert_noop!(
+			Timelock::dispatch_sealed(Origin::signed(3), 0, secret, bound),
+			Error::<Test>::TaskNotFound
+		);
+	});
+}
+
+#[test]
+fn dispatch_sealed_checks_the_plaintext() {
+	new_test_ext().execute_with(|| {
+		let (capsule, sealed, secret) = seal(5, &transfer(2, 50).encode());
+		let bound = 500_000_000;
+		assert_ok!(Timelock::schedule_sealed(Origin::signed(1), capsule.clone(), sealed, bound));
+		let dispatch = |secret: U256, max_weight: u64| {
+			Timelock::dispatch_sealed(Origin::signed(3), 0, secret, max_weight)
+		};
+
+		// the key of the release block is stored by the block after it.
+		System::set_block_number(6);
+		assert_noop!(dispatch(secret, bound), Error::<Test>::KeyNotReleased);
+		// a key stored for another block does not release the call.
+		MockReleasedKeys::set(4, released_key());
+		assert_noop!(dispatch(secret, bound), Error::<Test>::KeyNotReleased);
+
+		mine_after_release(5);
+		assert_noop!(dispatch(secret, bound - 1), Error::<Test>::MaxWeightTooLow);
+		assert_noop!(dispatch(secret + 1, bound), Error::<Test>::InvalidPlaintext);
+		// the secret is checked with the stored key, not the one chosen by the caller.
+		let g = U256::from(7u64);
+		let x = U256::from(12345u64);
+		let p = released_key().p;
+		let forged = RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x };
+		let forged_secret = elgamal::decrypt(&forged, &capsule).unwrap();
+		assert_noop!(dispatch(forged_secret, bound), Error::<Test>::InvalidPlaintext);
+		assert_eq!(Balances::free_balance(2), 1_000);
+	});
+}
+
+#[test]
+fn invalid_calls_are_discarded() {
+	new_test_ext().execute_with(|| {
+		let (capsule, sealed, secret) = seal(5, &transfer(2, 50).encode());
+		assert_ok!(Timelock::schedule_sealed(Origin::signed(1), capsule, sealed, 0));
+		let (capsule, sealed, _) = seal(5, b"not a call");
+		assert_ok!(Timelock::schedule_sealed(Origin::signed(1), capsule, sealed, 0));
+
+		mine_after_release(5);
+		assert_ok!(Timelock::dispatch_sealed(Origin::signed(3), 0, secret, 0));
+		let weight = transfer(2, 50).get_dispatch_info().weight;
+		System::assert_last_event(mock::Event::Timelock(crate::Event::CallOverweight(0, weight)));
+		assert_ok!(Timelock::dispatch_sealed(Origin::signed(3), 1, secret, 0));
+		System::assert_last_event(mock::Event::Timelock(crate::Event::CallUndecodable(1)));
+
+		// the calls are discarded with their deposits released.
+		assert_eq!(Balances::free_balance(1), 1_000);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Timelock::tasks(0), None);
+		assert_eq!(Timelock::tasks(1), None);
+	});
+}
+
+#[test]
+fn cancel_releases_the_deposit() {
+	new_test_ext().execute_with(|| {
+		let (capsule, sealed, _) = seal(5, &transfer(2, 50).encode());
+		assert_ok!(Timelock::schedule_sealed(Origin::signed(1), capsule, sealed, 0));
+
+		assert_noop!(Timelock::cancel(Origin::signed(2), 0), Error::<Test>::NotSubmitter);
+		assert_noop!(Timelock::cancel(Origin::signed(1), 1), Error::<Test>::TaskNotFound);
+		assert_ok!(Timelock::cancel(Origin::signed(1), 0));
+		System::assert_last_event(mock::Event::Timelock(crate::Event::CallCancelled(0)));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Timelock::tasks(0), None);
+	});
+}
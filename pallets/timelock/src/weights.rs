@@ -0,0 +1,78 @@
+//! Weights for pallet_trex_timelock
+//!
+//! Rough figures for the storage accesses, the ElGamal check and the ChaCha20-Poly1305 opening of
+//! a sealed call, not yet measured with the benchmarks of `benchmarking.rs`. The weight of a
+//! dispatched call is added to `dispatch_sealed` by the pallet.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_trex_timelock.
+pub trait TimelockWeight {
+    fn schedule_sealed(l: u32, ) -> Weight;
+    fn cancel() -> Weight;
+    fn dispatch_sealed(l: u32, ) -> Weight;
+}
+
+/// Weights for pallet_trex_timelock using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> TimelockWeight for SubstrateWeight<T> {
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXTimelock NextTaskId (r:1 w:1), Tasks (r:0 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn schedule_sealed(l: u32, ) -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add((2_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add(T::DbWeight::get().reads(3 as Weight))
+            .saturating_add(T::DbWeight::get().writes(3 as Weight))
+    }
+    // Storage: TREXTimelock Tasks (r:1 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn cancel() -> Weight {
+        (35_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(2 as Weight))
+    }
+    // Storage: TREXTimelock Tasks (r:1 w:1)
+    // Storage: TREXKeys Released (r:1 w:0)
+    // Storage: System Account (r:1 w:1)
+    fn dispatch_sealed(l: u32, ) -> Weight {
+        (70_000_000 as Weight)
+            .saturating_add((5_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add(T::DbWeight::get().reads(3 as Weight))
+            .saturating_add(T::DbWeight::get().writes(2 as Weight))
+    }
+}
+
+// For backwards compatibility and tests
+impl TimelockWeight for () {
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXTimelock NextTaskId (r:1 w:1), Tasks (r:0 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn schedule_sealed(l: u32, ) -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add((2_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add(RocksDbWeight::get().reads(3 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(3 as Weight))
+    }
+    // Storage: TREXTimelock Tasks (r:1 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn cancel() -> Weight {
+        (35_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(2 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(2 as Weight))
+    }
+    // Storage: TREXTimelock Tasks (r:1 w:1)
+    // Storage: TREXKeys Released (r:1 w:0)
+    // Storage: System Account (r:1 w:1)
+    fn dispatch_sealed(l: u32, ) -> Weight {
+        (70_000_000 as Weight)
+            .saturating_add((5_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add(RocksDbWeight::get().reads(3 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(2 as Weight))
+    }
+}
@@ -0,0 +1,37 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::unnecessary_mut_passed)]
+
+use codec::Codec;
+pub use pallet_trex::{CipherId, CiphertextRecord, MAX_PAGE_SIZE};
+use sp_std::vec::Vec;
+
+// Here we declare the runtime API. It is implemented it the `impl` block in
+// runtime amalgamator file (the `runtime/src/lib.rs`)
+sp_api::decl_runtime_apis! {
+	pub trait CiphertextApi<AccountId, Balance, BlockNumber> where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// The ciphertexts released at a height, a page of at most `limit` after `offset`.
+		fn ciphertexts_by_release(
+			release_height: BlockNumber,
+			offset: u32,
+			limit: u32,
+		) -> Vec<CiphertextRecord<AccountId, Balance, BlockNumber>>;
+		/// The ciphertexts submitted by an account.
+		fn ciphertexts_by_sender(
+			who: AccountId,
+			offset: u32,
+			limit: u32,
+		) -> Vec<CiphertextRecord<AccountId, Balance, BlockNumber>>;
+		/// The unpublished ciphertexts released within a range of heights (inclusive).
+		fn pending_releases(
+			from: BlockNumber,
+			to: BlockNumber,
+			offset: u32,
+			limit: u32,
+		) -> Vec<CiphertextRecord<AccountId, Balance, BlockNumber>>;
+	}
+}
@@ -0,0 +1,213 @@
+//! RPC interface to query the timed release ciphertexts of the TREX pallet.
+
+use codec::Codec;
+use jsonrpsee::{
+	core::{async_trait, Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorCode, ErrorObject},
+};
+pub use pallet_trex_runtime_api::CiphertextApi as CiphertextRuntimeApi;
+use pallet_trex_runtime_api::{CipherId, CiphertextRecord, MAX_PAGE_SIZE};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor},
+};
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+/// A ciphertext of the registry, big integers are encoded as decimal strings.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(bound(
+	serialize = "AccountId: Serialize, Balance: Display, BlockNumber: Serialize",
+	deserialize = "AccountId: Deserialize<'de>, Balance: FromStr, BlockNumber: Deserialize<'de>"
+))]
+pub struct CiphertextInfo<AccountId, Balance, BlockNumber> {
+	/// Identifier of the ciphertext.
+	pub id: CipherId,
+	/// Account which submitted the ciphertext.
+	pub submitter: AccountId,
+	/// Deposit reserved from the submitter.
+	#[serde(with = "serde_balance")]
+	pub deposit: Balance,
+	/// Height of the block whose key releases the ciphertext.
+	pub release_height: BlockNumber,
+	/// Bit length of the key.
+	pub bit_length: u32,
+	/// The prime modulus of the key.
+	pub p: String,
+	/// The ElGamal ciphertext `(c1, c2)` of the secret.
+	pub c1: String,
+	pub c2: String,
+	/// The encrypted payload following the key capsule.
+	pub ciphertext: Bytes,
+	/// The secret of the key capsule, once it is published.
+	pub plaintext: Option<String>,
+}
+
+impl<AccountId, Balance, BlockNumber> From<CiphertextRecord<AccountId, Balance, BlockNumber>>
+	for CiphertextInfo<AccountId, Balance, BlockNumber>
+{
+	fn from(record: CiphertextRecord<AccountId, Balance, BlockNumber>) -> Self {
+		let info = record.info;
+		Self {
+			id: record.id,
+			submitter: info.submitter,
+			deposit: info.deposit,
+			release_height: info.release_height,
+			bit_length: info.capsule.bit_length,
+			p: info.capsule.p.to_string(),
+			c1: info.capsule.c1.to_string(),
+			c2: info.capsule.c2.to_string(),
+			ciphertext: info.ciphertext.into(),
+			plaintext: record.plaintext.map(|secret| secret.to_string()),
+		}
+	}
+}
+
+mod serde_balance {
+	use serde::{Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer, T: std::fmt::Display>(
+		t: &T,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&t.to_string())
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>, T: std::str::FromStr>(
+		deserializer: D,
+	) -> Result<T, D::Error> {
+		let s = String::deserialize(deserializer)?;
+		s.parse::<T>().map_err(|_| serde::de::Error::custom("Parse from string failed"))
+	}
+}
+
+/// Queries of the ciphertexts are paginated, a page has at most `limit` ciphertexts (capped by
+/// `MAX_PAGE_SIZE`) after skipping `offset` of them.
+#[rpc(server, client)]
+pub trait CiphertextApi<BlockHash, AccountId, Balance, BlockNumber> {
+	/// Get the ciphertexts released by a block, ordered by their identifiers.
+	#[method(name = "trex_ciphertextsByRelease")]
+	fn ciphertexts_by_release(
+		&self,
+		block: BlockNumber,
+		offset: Option<u32>,
+		limit: Option<u32>,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<CiphertextInfo<AccountId, Balance, BlockNumber>>>;
+
+	/// Get the ciphertexts submitted by an account, ordered by their identifiers.
+	#[method(name = "trex_ciphertextsBySender")]
+	fn ciphertexts_by_sender(
+		&self,
+		account: AccountId,
+		offset: Option<u32>,
+		limit: Option<u32>,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<CiphertextInfo<AccountId, Balance, BlockNumber>>>;
+
+	/// Get the ciphertexts released within a range of blocks (inclusive) whose plaintexts are not
+	/// published yet, ordered by their release heights and identifiers.
+	#[method(name = "trex_pendingReleases")]
+	fn pending_releases(
+		&self,
+		from: BlockNumber,
+		to: BlockNumber,
+		offset: Option<u32>,
+		limit: Option<u32>,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<CiphertextInfo<AccountId, Balance, BlockNumber>>>;
+}
+
+pub struct Ciphertexts<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Ciphertexts<C, B> {
+	/// Create new `Ciphertexts` instance with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, B> Ciphertexts<C, B>
+where
+	B: BlockT,
+	C: HeaderBackend<B>,
+{
+	fn block_id(&self, at: Option<<B as BlockT>::Hash>) -> BlockId<B> {
+		BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash))
+	}
+}
+
+fn into_rpc<AccountId, Balance, BlockNumber>(
+	result: Result<Vec<CiphertextRecord<AccountId, Balance, BlockNumber>>, sp_api::ApiError>,
+) -> RpcResult<Vec<CiphertextInfo<AccountId, Balance, BlockNumber>>> {
+	result
+		.map(|records| records.into_iter().map(Into::into).collect())
+		.map_err(|e| {
+			JsonRpseeError::Call(CallError::Custom(ErrorObject::owned(
+				ErrorCode::InternalError.code(),
+				format!("Unable to query the ciphertexts: {:?}", e),
+				None::<()>,
+			)))
+		})
+}
+
+#[async_trait]
+impl<C, B, AccountId, Balance>
+	CiphertextApiServer<<B as BlockT>::Hash, AccountId, Balance, NumberFor<B>> for Ciphertexts<C, B>
+where
+	B: BlockT + 'static,
+	C: HeaderBackend<B> + ProvideRuntimeApi<B> + Send + Sync + 'static,
+	C::Api: CiphertextRuntimeApi<B, AccountId, Balance, NumberFor<B>>,
+	AccountId: Codec + Clone + Send + Sync + 'static + Serialize + for<'de> Deserialize<'de>,
+	Balance: Codec + Display + FromStr + Send + Sync + 'static,
+{
+	fn ciphertexts_by_release(
+		&self,
+		block: NumberFor<B>,
+		offset: Option<u32>,
+		limit: Option<u32>,
+		at: Option<<B as BlockT>::Hash>,
+	) -> RpcResult<Vec<CiphertextInfo<AccountId, Balance, NumberFor<B>>>> {
+		let api = self.client.runtime_api();
+		let at = self.block_id(at);
+		let limit = limit.unwrap_or(MAX_PAGE_SIZE);
+		into_rpc(api.ciphertexts_by_release(&at, block, offset.unwrap_or(0), limit))
+	}
+
+	fn ciphertexts_by_sender(
+		&self,
+		account: AccountId,
+		offset: Option<u32>,
+		limit: Option<u32>,
+		at: Option<<B as BlockT>::Hash>,
+	) -> RpcResult<Vec<CiphertextInfo<AccountId, Balance, NumberFor<B>>>> {
+		let api = self.client.runtime_api();
+		let at = self.block_id(at);
+		let limit = limit.unwrap_or(MAX_PAGE_SIZE);
+		into_rpc(api.ciphertexts_by_sender(&at, account, offset.unwrap_or(0), limit))
+	}
+
+	fn pending_releases(
+		&self,
+		from: NumberFor<B>,
+		to: NumberFor<B>,
+		offset: Option<u32>,
+		limit: Option<u32>,
+		at: Option<<B as BlockT>::Hash>,
+	) -> RpcResult<Vec<CiphertextInfo<AccountId, Balance, NumberFor<B>>>> {
+		let api = self.client.runtime_api();
+		let at = self.block_id(at);
+		let limit = limit.unwrap_or(MAX_PAGE_SIZE);
+		into_rpc(api.pending_releases(&at, from, to, offset.unwrap_or(0), limit))
+	}
+}
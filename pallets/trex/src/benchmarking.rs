@@ -1,20 +1,93 @@
-//! Benchmarking setup for pallet-difficulty
+//! Benchmarking setup for pallet-trex
 
 use super::*;
 
 #[allow(unused)]
-use crate::Pallet as Template;
-use frame_benchmarking::{benchmarks, whitelisted_caller};
+use crate::Pallet as TREX;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
 use frame_system::RawOrigin;
+use sp_runtime::traits::Bounded;
+use sp_std::vec;
+
+/// A key over a 48 bit safe prime, the cost of the arithmetic is dominated by the big integers.
+fn bench_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(1234567u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+/// A capsule of a secret encrypted to the key released at the target height.
+fn bench_capsule(target: u32) -> KeyCapsule {
+	elgamal::encrypt(&bench_key(), target, U256::from(42u32), U256::from(987654u32))
+}
+
+fn funded<T: Config>(who: &T::AccountId) {
+	T::Currency::make_free_balance_be(who, BalanceOf::<T>::max_value() / 2u32.into());
+}
+
+/// Store a ciphertext of the maximum length for the caller.
+fn store_cipher<T: Config>(who: &T::AccountId, capsule: KeyCapsule) {
+	let ciphertext = vec![0u8; T::MaxCipherLen::get() as usize];
+	TREX::<T>::send_trex_data(RawOrigin::Signed(who.clone()).into(), capsule, ciphertext)
+		.expect("the caller is funded");
+}
 
 benchmarks! {
-	send_predcit {
-		let s in vec![vec![1,2,3,4,5],vec![3,4,5,6,7]];
+	send_trex_data {
+		let l in 0 .. T::MaxCipherLen::get();
 		let caller: T::AccountId = whitelisted_caller();
-	}: _(RawOrigin::Signed(caller), s)
+		funded::<T>(&caller);
+		let release_height: T::BlockNumber = 10u32.into();
+	}: _(RawOrigin::Signed(caller.clone()), bench_capsule(10), vec![0u8; l as usize])
+	verify {
+		assert_eq!(CiphertextsBySender::<T>::get(&caller, 0), Some(release_height));
+	}
+
+	withdraw_ciphertext {
+		let caller: T::AccountId = whitelisted_caller();
+		funded::<T>(&caller);
+		store_cipher::<T>(&caller, bench_capsule(10));
+	}: _(RawOrigin::Signed(caller.clone()), 0)
+	verify {
+		assert_eq!(CiphertextsBySender::<T>::get(&caller, 0), None);
+	}
+
+	// A step of the expiry sweep which removes a ciphertext.
+	expire_ciphertext {
+		let caller: T::AccountId = account("submitter", 0, 0);
+		funded::<T>(&caller);
+		let release_height: T::BlockNumber = 1u32.into();
+		frame_system::Pallet::<T>::set_block_number(0u32.into());
+		store_cipher::<T>(&caller, bench_capsule(1));
+	}: {
+		let id = Ciphertexts::<T>::iter_key_prefix(release_height).next();
+		id.and_then(|id| TREX::<T>::remove_cipher(release_height, id));
+	}
+	verify {
+		assert_eq!(CiphertextsBySender::<T>::get(&caller, 0), None);
+	}
+
+	publish_plaintexts {
+		let n in 1 .. MAX_PLAINTEXTS_PER_TX as u32;
+		let caller: T::AccountId = account("submitter", 0, 0);
+		funded::<T>(&caller);
+		let key = bench_key();
+		let release_height: T::BlockNumber = 1u32.into();
+		frame_system::Pallet::<T>::set_block_number(0u32.into());
+		let mut plaintexts = vec![];
+		for i in 0 .. n {
+			let secret = U256::from(i + 1);
+			let capsule = elgamal::encrypt(&key, 1, secret, U256::from(987654u32 + i));
+			store_cipher::<T>(&caller, capsule);
+			plaintexts.push((i as CipherId, secret));
+		}
+		frame_system::Pallet::<T>::set_block_number(release_height);
+		T::ReleasedKeys::insert_released_key(release_height, key);
+	}: _(RawOrigin::None, release_height, plaintexts)
 	verify {
-		assert_eq!(PredictStorage::<T>::get(), Some(s));
+		assert_eq!(Plaintexts::<T>::iter().count(), n as usize);
 	}
 
-	impl_benchmark_test_suite!(Template, crate::mock::new_test_ext(), crate::mock::Test);
+	impl_benchmark_test_suite!(TREX, crate::mock::new_test_ext(), crate::mock::Test);
 }
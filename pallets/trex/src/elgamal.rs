@@ -0,0 +1,111 @@
+//! ElGamal arithmetic over the keys of TREX keychain, used to decrypt and verify the key capsules
+//! of timed release envelopes inside the runtime.
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_core::{U256, U512};
+use sp_runtime::RuntimeDebug;
+use sp_std::{convert::TryFrom, vec, vec::Vec};
+use trex_constants::{MAX_DIFFICULTY, MIN_DIFFICULTY};
+use trex_io::RawReleasedKey;
+
+/// A secret encrypted to the public key of a target block, as in the envelope of `trex-client`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct KeyCapsule {
+	/// Number of the block releasing the private key.
+	pub target: u32,
+	/// Bit length of the key, which is the mining difficulty of the target block.
+	pub bit_length: u32,
+	/// The prime modulus of the key.
+	pub p: U256,
+	pub c1: U256,
+	pub c2: U256,
+}
+
+impl KeyCapsule {
+	/// Whether the bit length is a valid mining difficulty, so the key can be mined.
+	pub fn has_valid_bit_length(&self) -> bool {
+		(MIN_DIFFICULTY..=MAX_DIFFICULTY).contains(&(self.bit_length as u128))
+	}
+
+	/// Whether the ciphertext values are below the declared modulus.
+	pub fn is_reduced(&self) -> bool {
+		self.p > U256::one() && self.c1 < self.p && self.c2 < self.p
+	}
+}
+
+/// Decode the key capsules of a SCALE encoded envelope, the first byte is the version of the
+/// envelope format. Returns an empty list for an unknown format.
+pub fn capsules(envelope: &[u8]) -> Vec<KeyCapsule> {
+	split_envelope(envelope).map(|(capsules, _)| capsules).unwrap_or_default()
+}
+
+/// Split a SCALE encoded envelope into its key capsules and the encrypted payload following
+/// them. Returns `None` for an unknown format.
+pub fn split_envelope(envelope: &[u8]) -> Option<(Vec<KeyCapsule>, &[u8])> {
+	let mut input = envelope;
+	let capsules = match u8::decode(&mut input).ok()? {
+		1 => vec![KeyCapsule::decode(&mut input).ok()?],
+		2 => Vec::<KeyCapsule>::decode(&mut input).ok()?,
+		_ => return None,
+	};
+	Some((capsules, input))
+}
+
+fn mul_mod(a: U256, b: U256, p: U256) -> U256 {
+	let rem = a.full_mul(b) % U512::from(p);
+	// the remainder is always smaller than the modulus.
+	U256::try_from(rem).unwrap_or_default()
+}
+
+/// Modular exponentiation by squaring.
+pub fn pow_mod(base: U256, exp: U256, p: U256) -> U256 {
+	if p <= U256::one() {
+		return U256::zero()
+	}
+	let base = base % p;
+	let mut result = U256::one();
+	for i in (0..exp.bits()).rev() {
+		result = mul_mod(result, result, p);
+		if exp.bit(i) {
+			result = mul_mod(result, base, p);
+		}
+	}
+	result
+}
+
+/// Encrypt a secret smaller than the modulus to a public key with the ephemeral key `k`.
+pub fn encrypt(key: &RawReleasedKey, target: u32, secret: U256, k: U256) -> KeyCapsule {
+	let c1 = pow_mod(key.g, k, key.p);
+	let c2 = mul_mod(secret, pow_mod(key.h, k, key.p), key.p);
+	KeyCapsule { target, bit_length: key.bit_length, p: key.p, c1, c2 }
+}
+
+/// Check the private key is the discrete logarithm of its public key.
+pub fn verify_key(key: &RawReleasedKey) -> bool {
+	key.p > U256::one() && key.h < key.p && pow_mod(key.g, key.x, key.p) == key.h
+}
+
+fn matches(key: &RawReleasedKey, capsule: &KeyCapsule) -> bool {
+	capsule.p == key.p &&
+		capsule.bit_length == key.bit_length &&
+		capsule.c1 < key.p &&
+		capsule.c2 < key.p
+}
+
+/// Decrypt the secret of a capsule with a released key, the inverse of the shared secret is
+/// computed with the Fermat's little theorem since the modulus is a prime.
+pub fn decrypt(key: &RawReleasedKey, capsule: &KeyCapsule) -> Option<U256> {
+	if !matches(key, capsule) || key.x >= key.p {
+		return None
+	}
+	let exp = key.p - U256::one() - key.x;
+	let secret = mul_mod(capsule.c2, pow_mod(capsule.c1, exp, key.p), key.p);
+	Some(secret)
+}
+
+/// Check a secret is the plaintext of a capsule, which is `c2 = m * c1^x mod p`.
+pub fn verify(key: &RawReleasedKey, capsule: &KeyCapsule, secret: U256) -> bool {
+	matches(key, capsule) &&
+		secret < key.p &&
+		mul_mod(secret, pow_mod(capsule.c1, key.x, key.p), key.p) == capsule.c2
+}
@@ -0,0 +1,51 @@
+//! The symmetric part of the timed release envelopes of `trex-client` inside the runtime.
+//! A payload is encrypted with ChaCha20-Poly1305 under the key derived from the secret of its key
+//! capsule, the sealed payload is a nonce followed by the ciphertext and its authentication tag.
+use crate::KeyCapsule;
+use chacha20poly1305::{
+	aead::{Aead, NewAead, Payload},
+	ChaCha20Poly1305, Key, Nonce,
+};
+use codec::Encode;
+use sp_core::U256;
+use sp_std::vec::Vec;
+
+/// Context string of the key derivation function, the same as `trex-client`.
+pub const KDF_CONTEXT: &str = "TREX timed release envelope symmetric key";
+
+/// Length of the nonce preceding the ciphertext.
+pub const NONCE_LEN: usize = 12;
+
+/// Length of the authentication tag of the ciphertext.
+pub const TAG_LEN: usize = 16;
+
+/// Derive the symmetric key from the secret of a key capsule.
+pub fn derive_key(secret: U256) -> [u8; 32] {
+	let mut hasher = blake3::Hasher::new_derive_key(KDF_CONTEXT);
+	hasher.update(&secret.encode());
+	*hasher.finalize().as_bytes()
+}
+
+/// Seal a payload with the secret of its key capsule, the encoded capsule authenticates the
+/// ciphertext.
+pub fn seal(capsule: &KeyCapsule, secret: U256, nonce: [u8; NONCE_LEN], payload: &[u8]) -> Vec<u8> {
+	let aad = capsule.encode();
+	let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&derive_key(secret)))
+		.encrypt(Nonce::from_slice(&nonce), Payload { msg: payload, aad: &aad })
+		// the cipher only fails on the payloads longer than 256 GiB.
+		.unwrap_or_default();
+	[&nonce[..], &ciphertext].concat()
+}
+
+/// Open a sealed payload with the secret of its key capsule, returns `None` if the secret or the
+/// capsule does not authenticate it.
+pub fn open(capsule: &KeyCapsule, secret: U256, sealed: &[u8]) -> Option<Vec<u8>> {
+	if sealed.len() < NONCE_LEN + TAG_LEN {
+		return None
+	}
+	let (nonce, msg) = sealed.split_at(NONCE_LEN);
+	let aad = capsule.encode();
+	ChaCha20Poly1305::new(Key::from_slice(&derive_key(secret)))
+		.decrypt(Nonce::from_slice(nonce), Payload { msg, aad: &aad })
+		.ok()
+}
@@ -3,7 +3,25 @@
 /// Edit this file to define custom logic or remove it if it is not needed.
 /// Learn more about FRAME and the core library of Substrate FRAME pallets:
 /// <https://docs.substrate.io/v3/runtime/frame>
+use frame_support::{
+	traits::{Currency, Get, ReservableCurrency},
+	weights::Weight,
+};
+use frame_system::offchain::{SendTransactionTypes, SubmitTransaction};
 pub use pallet::*;
+use sp_core::U256;
+use sp_runtime::{
+	traits::{One, Saturating},
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionValidity, ValidTransaction,
+	},
+};
+use sp_std::{cmp::min, vec::Vec};
+pub use trex_io::RawReleasedKey;
+pub mod elgamal;
+pub use elgamal::KeyCapsule;
+pub mod envelope;
+pub mod migrations;
 pub mod weights;
 pub use weights::TREXWeight;
 
@@ -23,35 +41,198 @@ pub mod pallet {
 	use frame_system::pallet_prelude::*;
 	use sp_std::vec::Vec;
 
+	/// The current storage version.
+	pub(crate) const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+	/// Identifier of a ciphertext in the registry.
+	pub type CipherId = u64;
+
+	/// Maximum number of plaintexts published by a transaction of the offchain worker.
+	pub const MAX_PLAINTEXTS_PER_TX: usize = 64;
+
+	/// Maximum number of ciphertexts returned by a query of the runtime API.
+	pub const MAX_PAGE_SIZE: u32 = 100;
+
+	/// Maximum number of release heights scanned by a query of the pending releases.
+	pub const MAX_RELEASE_RANGE: u32 = 1000;
+
 	/// Configure the pallet by specifying the parameters and types on which it depends.
 	#[pallet::config]
-	pub trait Config: frame_system::Config {
+	pub trait Config: frame_system::Config + SendTransactionTypes<Call<Self>> {
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
 
+		/// Maximum length of a ciphertext in bytes.
+		#[pallet::constant]
+		type MaxCipherLen: Get<u32>;
+
 		/// Weight information for extrinsics in this pallet.
 		type TREXWeight: TREXWeight;
+
+		/// The currency to reserve the storage deposits of ciphertexts.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// The deposit reserved for each stored ciphertext.
+		#[pallet::constant]
+		type DepositPerItem: Get<BalanceOf<Self>>;
+
+		/// The deposit reserved for each byte of a stored ciphertext.
+		#[pallet::constant]
+		type DepositPerByte: Get<BalanceOf<Self>>;
+
+		/// Number of blocks a ciphertext is kept after its release block.
+		#[pallet::constant]
+		type RetentionPeriod: Get<Self::BlockNumber>;
+
+		/// Maximum number of steps of the expiry sweep in a block.
+		#[pallet::constant]
+		type MaxExpiredPerBlock: Get<u32>;
+
+		/// Priority of the unsigned transactions publishing plaintexts.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+
+		/// The keys released by the mined blocks, which the published plaintexts are checked
+		/// against.
+		type ReleasedKeys: ReleasedKeys<Self::BlockNumber>;
 	}
 
+	/// Type alias for currency balance.
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
 	#[pallet::pallet]
-	#[pallet::without_storage_info]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(PhantomData<T>);
 
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_idle(now: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			Self::sweep_expired(now, remaining_weight)
+		}
+
+		/// Decrypt the ciphertexts released by the parent of the imported block, whose key is
+		/// stored by the block, and publish the plaintexts.
+		fn offchain_worker(now: T::BlockNumber) {
+			// nothing to do without a key, e.g. the genesis block releases none.
+			let _ = Self::publish_released(now);
+		}
+	}
+
+	// Struct for holding TREX information.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct TREXData<T: Config> {
+		pub cipher_list: BoundedVec<u8, T::MaxCipherLen>,
+		pub from: T::AccountId,
+	}
+
+	/// A ciphertext in the registry with the metadata needed to decrypt it.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct CipherInfo<AccountId, Balance, BlockNumber, Ciphertext> {
+		/// Account which submitted the ciphertext.
+		pub submitter: AccountId,
+		/// Deposit reserved from the submitter, which is returned once the entry is removed.
+		pub deposit: Balance,
+		/// Height of the block whose key releases the ciphertext.
+		pub release_height: BlockNumber,
+		/// The key capsule encrypted to the key of the release height.
+		pub capsule: KeyCapsule,
+		/// The encrypted payload following the key capsule in an `Envelope` of `trex-client`.
+		pub ciphertext: Ciphertext,
+	}
+
+	/// A ciphertext bounded by the maximum length.
+	pub type CiphertextOf<T> = BoundedVec<u8, <T as Config>::MaxCipherLen>;
+
+	pub type CipherInfoOf<T> = CipherInfo<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+		CiphertextOf<T>,
+	>;
+
+	/// A ciphertext of the registry returned by the runtime API, with its identifier and the
+	/// plaintext published for it.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct CiphertextRecord<AccountId, Balance, BlockNumber> {
+		/// Identifier of the ciphertext.
+		pub id: CipherId,
+		/// The ciphertext and its metadata.
+		pub info: CipherInfo<AccountId, Balance, BlockNumber, Vec<u8>>,
+		/// The secret of the key capsule, once it is published by the offchain worker.
+		pub plaintext: Option<U256>,
+	}
+
+	pub type CiphertextRecordOf<T> = CiphertextRecord<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+	>;
+
 	// The pallet's runtime storage items.
 	// https://docs.substrate.io/v3/runtime/storage
 	// Learn more about declaring storage items:
 	// https://docs.substrate.io/v3/runtime/storage#declaring-storage-items
+	/// Single value storage of the last submission, only kept for the migration to the registry.
+	#[pallet::storage]
+	#[pallet::unbounded]
+	pub(crate) type TREXStorage<T> = StorageValue<_, Vec<u8>>;
+
+	/// The identifier of the next ciphertext.
+	#[pallet::storage]
+	#[pallet::getter(fn next_cipher_id)]
+	pub type NextCipherId<T> = StorageValue<_, CipherId, ValueQuery>;
+
+	/// Ciphertexts keyed by the release block height and their identifier.
+	#[pallet::storage]
+	#[pallet::getter(fn ciphertexts)]
+	pub type Ciphertexts<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		Twox64Concat,
+		CipherId,
+		CipherInfoOf<T>,
+	>;
+
+	/// The release height from which the expiry sweep continues.
+	#[pallet::storage]
+	#[pallet::getter(fn expiry_cursor)]
+	pub type ExpiryCursor<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
+
+	/// Secrets of the key capsules decrypted with the released keys, which derive the symmetric
+	/// keys of the envelopes.
 	#[pallet::storage]
-	#[pallet::getter(fn something)]
-	pub type TREXStorage<T> = StorageValue<_, Vec<u8>>;
+	#[pallet::getter(fn plaintexts)]
+	pub type Plaintexts<T: Config> = StorageMap<_, Twox64Concat, CipherId, U256>;
+
+	/// Index of the ciphertexts of each submitter, which maps to the release block height.
+	#[pallet::storage]
+	#[pallet::getter(fn ciphertexts_by_sender)]
+	pub type CiphertextsBySender<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Twox64Concat,
+		CipherId,
+		T::BlockNumber,
+	>;
 
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/v3/runtime/events-and-errors
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
-		/// TREX Data Send Event
-		TREXDataSent(T::AccountId, Vec<u8>),
+		/// TREX Data Send Event. \[submitter, cipher_id, release_height\]
+		TREXDataSent(T::AccountId, CipherId, T::BlockNumber),
+		/// A ciphertext is withdrawn by its submitter. \[submitter, cipher_id, deposit\]
+		CiphertextWithdrawn(T::AccountId, CipherId, BalanceOf<T>),
+		/// A ciphertext is removed after its retention period. \[submitter, cipher_id, release_height\]
+		CiphertextExpired(T::AccountId, CipherId, T::BlockNumber),
+		/// The plaintext of a released ciphertext is published. \[cipher_id, release_height\]
+		PlaintextPublished(CipherId, T::BlockNumber),
 	}
 
 	// Errors inform users that something went wrong.
@@ -61,15 +242,28 @@ pub mod pallet {
 		NoneValue,
 		/// Errors should have helpful documentation associated with them.
 		TREXInfoSentOverflow,
-	}
-
-	// Struct for holding TREX information.
-	#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug, TypeInfo)]
-	#[scale_info(skip_type_params(T))]
-	#[codec(mel_bound())]
-	pub struct TREXData<T: Config> {
-		pub cipher_list: Vec<u8>,
-		pub from: T::AccountId,
+		/// The submitter can not afford the storage deposit of the ciphertext.
+		InsufficientDeposit,
+		/// No ciphertext of the submitter is found with the identifier.
+		CiphertextNotFound,
+		/// The key of the release height is not released yet.
+		KeyNotReleased,
+		/// The private key does not match its public key.
+		InvalidReleasedKey,
+		/// The plaintext is not the decryption of the ciphertext.
+		InvalidPlaintext,
+		/// The plaintext of the ciphertext is already published.
+		PlaintextAlreadyPublished,
+		/// Too many plaintexts are published in a transaction.
+		TooManyPlaintexts,
+		/// The ciphertext is longer than `MaxCipherLen`.
+		CiphertextTooLong,
+		/// The target height of the key capsule is not in the future.
+		TargetNotInFuture,
+		/// The bit length of the key capsule is not a valid mining difficulty.
+		InvalidBitLength,
+		/// A value of the key capsule is not below its modulus.
+		InvalidCapsule,
 	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -77,13 +271,16 @@ pub mod pallet {
 	// Dispatchable functions must be annotated with a weight and must return a DispatchResult.
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
-		/// An example dispatchable that takes a singles value as a parameter, writes the value to
-		/// storage and emits an event. This function must be dispatched by a signed extrinsic.
+		/// Send timed release data to the chain, which must be dispatched by a signed extrinsic.
+		/// The `capsule` records the target block, the key bit length, the modulus `p` and the
+		/// ElGamal ciphertext `(c1, c2)` of the secret, and the `cipher_list` is the rest of an
+		/// `Envelope` of `trex-client`, so any client can decrypt it once the key is released.
+		/// The ciphertext is stored in the registry under the target height of the capsule.
 		/// #[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
-		#[pallet::weight(T::TREXWeight::send_trex_data())]
+		#[pallet::weight(T::TREXWeight::send_trex_data(cipher_list.len() as u32))]
 		pub fn send_trex_data(
 			origin: OriginFor<T>,
-			_from: T::AccountId,
+			capsule: KeyCapsule,
 			cipher_list: Vec<u8>,
 		) -> DispatchResult {
 			// Check that the extrinsic was signed and get the signer.
@@ -91,20 +288,303 @@ pub mod pallet {
 			// https://docs.substrate.io/v3/runtime/origins
 			let who = ensure_signed(origin)?;
 
-			//construct InfoData Struct for TREXStorage
-			let owner = who.clone();
-			let ciphers = cipher_list.clone();
-			let trex_data = TREXData::<T> { cipher_list: ciphers, from: owner };
+			let release_height = Self::check_capsule(&capsule)?;
+			let ciphertext: CiphertextOf<T> =
+				cipher_list.try_into().map_err(|_| Error::<T>::CiphertextTooLong)?;
+			let deposit = Self::deposit_of(ciphertext.len());
+			T::Currency::reserve(&who, deposit).map_err(|_| Error::<T>::InsufficientDeposit)?;
 
-			//encode InfoData instance to vec<u8>
-			let trex_byte_data = trex_data.encode();
-			// Update storage.
-			<TREXStorage<T>>::put(&trex_byte_data);
+			let info = CipherInfo {
+				submitter: who.clone(),
+				deposit,
+				release_height,
+				capsule,
+				ciphertext,
+			};
+			let id = Self::insert_cipher(info)?;
 
 			// Emit an event.
-			Self::deposit_event(Event::TREXDataSent(who, trex_byte_data));
+			Self::deposit_event(Event::TREXDataSent(who, id, release_height));
 			// Return a successful DispatchResultWithPostInfo
 			Ok(())
 		}
+
+		/// Remove a ciphertext of the signer from the registry and release its deposit.
+		#[pallet::weight(T::TREXWeight::withdraw_ciphertext())]
+		pub fn withdraw_ciphertext(origin: OriginFor<T>, id: CipherId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let release_height =
+				CiphertextsBySender::<T>::get(&who, id).ok_or(Error::<T>::CiphertextNotFound)?;
+			let info =
+				Self::remove_cipher(release_height, id).ok_or(Error::<T>::CiphertextNotFound)?;
+
+			Self::deposit_event(Event::CiphertextWithdrawn(who, id, info.deposit));
+			Ok(())
+		}
+
+		/// Publish the plaintexts of the ciphertexts released at `release_height`, which must be
+		/// dispatched by an unsigned transaction of the offchain worker.
+		///
+		/// Every plaintext is verified against its key capsule with the key stored on chain for
+		/// the release height, whose modulus must match the one declared by the capsule.
+		#[pallet::weight(T::TREXWeight::publish_plaintexts(plaintexts.len() as u32))]
+		pub fn publish_plaintexts(
+			origin: OriginFor<T>,
+			release_height: T::BlockNumber,
+			plaintexts: Vec<(CipherId, U256)>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			Self::check_plaintexts(release_height, &plaintexts)?;
+
+			for (id, secret) in plaintexts {
+				Plaintexts::<T>::insert(id, secret);
+				Self::deposit_event(Event::PlaintextPublished(id, release_height));
+			}
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			if let Call::publish_plaintexts { release_height, plaintexts } = call {
+				if Self::check_plaintexts(*release_height, plaintexts).is_err() {
+					return InvalidTransaction::Call.into()
+				}
+				let ids: Vec<CipherId> = plaintexts.iter().map(|(id, _)| *id).collect();
+				ValidTransaction::with_tag_prefix("TrexPlaintexts")
+					.priority(T::UnsignedPriority::get())
+					.and_provides((release_height, ids))
+					.longevity(5)
+					.propagate(true)
+					.build()
+			} else {
+				InvalidTransaction::Call.into()
+			}
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The deposit to store a ciphertext of the given length.
+	pub fn deposit_of(len: usize) -> BalanceOf<T> {
+		let bytes: BalanceOf<T> = (len as u32).into();
+		T::DepositPerItem::get().saturating_add(T::DepositPerByte::get().saturating_mul(bytes))
+	}
+
+	/// Check a key capsule targets a key which is not released yet, whose bit length is a valid
+	/// mining difficulty, returns the release height of the capsule.
+	pub(crate) fn check_capsule(capsule: &KeyCapsule) -> Result<T::BlockNumber, Error<T>> {
+		let release_height = T::BlockNumber::from(capsule.target);
+		if release_height <= frame_system::Pallet::<T>::block_number() {
+			return Err(Error::<T>::TargetNotInFuture)
+		}
+		if !capsule.has_valid_bit_length() {
+			return Err(Error::<T>::InvalidBitLength)
+		}
+		if !capsule.is_reduced() {
+			return Err(Error::<T>::InvalidCapsule)
+		}
+		Ok(release_height)
+	}
+
+	/// Store a ciphertext in the registry and the index of its submitter, returns its identifier.
+	pub(crate) fn insert_cipher(info: CipherInfoOf<T>) -> Result<CipherId, Error<T>> {
+		let id = NextCipherId::<T>::get();
+		let next = id.checked_add(1).ok_or(Error::<T>::TREXInfoSentOverflow)?;
+		NextCipherId::<T>::put(next);
+		CiphertextsBySender::<T>::insert(&info.submitter, id, info.release_height);
+		Ciphertexts::<T>::insert(info.release_height, id, info);
+		Ok(id)
+	}
+
+	/// Remove a ciphertext and its index from the registry, and unreserve its deposit.
+	pub(crate) fn remove_cipher(
+		release_height: T::BlockNumber,
+		id: CipherId,
+	) -> Option<CipherInfoOf<T>> {
+		let info = Ciphertexts::<T>::take(release_height, id)?;
+		CiphertextsBySender::<T>::remove(&info.submitter, id);
+		Plaintexts::<T>::remove(id);
+		T::Currency::unreserve(&info.submitter, info.deposit);
+		Some(info)
+	}
+
+	/// Remove the ciphertexts whose retention period is over and refund their deposits.
+	///
+	/// The sweep walks the release heights from `ExpiryCursor`, each step either removes one
+	/// ciphertext or skips an empty height, and the steps are bounded by `MaxExpiredPerBlock`
+	/// and the remaining weight of the block.
+	pub(crate) fn sweep_expired(now: T::BlockNumber, remaining_weight: Weight) -> Weight {
+		let base = T::DbWeight::get().reads_writes(1, 1);
+		let step = T::TREXWeight::expire_ciphertext();
+		if remaining_weight < base.saturating_add(step) {
+			return 0
+		}
+		let budget = min(
+			T::MaxExpiredPerBlock::get() as Weight,
+			(remaining_weight - base) / step.max(1),
+		);
+
+		let retention = T::RetentionPeriod::get();
+		let mut height = ExpiryCursor::<T>::get();
+		let mut steps: Weight = 0;
+		while steps < budget && height.saturating_add(retention) < now {
+			steps += 1;
+			match Ciphertexts::<T>::iter_key_prefix(height).next() {
+				Some(id) =>
+					if let Some(info) = Self::remove_cipher(height, id) {
+						Self::deposit_event(Event::CiphertextExpired(info.submitter, id, height));
+					},
+				None => height = height.saturating_add(One::one()),
+			}
+		}
+		ExpiryCursor::<T>::put(height);
+		base.saturating_add(step.saturating_mul(steps))
+	}
+
+	/// Verify the plaintexts published for the ciphertexts released at a height, with the key
+	/// stored for the height.
+	pub(crate) fn check_plaintexts(
+		release_height: T::BlockNumber,
+		plaintexts: &[(CipherId, U256)],
+	) -> Result<(), Error<T>> {
+		if plaintexts.len() > MAX_PLAINTEXTS_PER_TX {
+			return Err(Error::<T>::TooManyPlaintexts)
+		}
+		let key =
+			T::ReleasedKeys::released_key(release_height).ok_or(Error::<T>::KeyNotReleased)?;
+		if !elgamal::verify_key(&key) {
+			return Err(Error::<T>::InvalidReleasedKey)
+		}
+		for (id, secret) in plaintexts {
+			let info =
+				Ciphertexts::<T>::get(release_height, id).ok_or(Error::<T>::CiphertextNotFound)?;
+			if Plaintexts::<T>::contains_key(id) {
+				return Err(Error::<T>::PlaintextAlreadyPublished)
+			}
+			if !elgamal::verify(&key, &info.capsule, *secret) {
+				return Err(Error::<T>::InvalidPlaintext)
+			}
+		}
+		Ok(())
+	}
+
+	/// Read the key released by the parent of the current block, decrypt the pending ciphertexts
+	/// of the parent height and submit the plaintexts with an unsigned transaction.
+	pub(crate) fn publish_released(now: T::BlockNumber) -> Result<(), &'static str> {
+		let release = now.saturating_sub(One::one());
+		if Ciphertexts::<T>::iter_key_prefix(release).next().is_none() {
+			return Ok(())
+		}
+		let key = T::ReleasedKeys::released_key(release).ok_or("The key is not released")?;
+
+		let plaintexts: Vec<(CipherId, U256)> = Ciphertexts::<T>::iter_prefix(release)
+			.filter(|(id, _)| !Plaintexts::<T>::contains_key(id))
+			.filter_map(|(id, info)| {
+				elgamal::decrypt(&key, &info.capsule).map(|secret| (id, secret))
+			})
+			.take(MAX_PLAINTEXTS_PER_TX)
+			.collect();
+		if plaintexts.is_empty() {
+			return Ok(())
+		}
+		let call = Call::publish_plaintexts { release_height: release, plaintexts };
+		SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into())
+			.map_err(|_| "Failed to submit the plaintexts")
+	}
+
+	/// The ciphertexts released at a height ordered by their identifiers, skipping `offset` of
+	/// them and returning at most `limit` (capped by `MAX_PAGE_SIZE`).
+	pub fn ciphertexts_by_release(
+		release_height: T::BlockNumber,
+		offset: u32,
+		limit: u32,
+	) -> Vec<CiphertextRecordOf<T>> {
+		let keys = Ciphertexts::<T>::iter_key_prefix(release_height)
+			.map(|id| (release_height, id))
+			.collect();
+		Self::page(keys, offset, limit)
+	}
+
+	/// The ciphertexts submitted by an account ordered by their identifiers.
+	pub fn ciphertexts_by_sender(
+		who: T::AccountId,
+		offset: u32,
+		limit: u32,
+	) -> Vec<CiphertextRecordOf<T>> {
+		let keys = CiphertextsBySender::<T>::iter_prefix(who)
+			.map(|(id, release_height)| (release_height, id))
+			.collect();
+		Self::page(keys, offset, limit)
+	}
+
+	/// The ciphertexts released within a range of heights (inclusive) whose plaintexts are not
+	/// published yet, ordered by their release heights and identifiers. The range is capped to
+	/// `MAX_RELEASE_RANGE` heights from `from`.
+	pub fn pending_releases(
+		from: T::BlockNumber,
+		to: T::BlockNumber,
+		offset: u32,
+		limit: u32,
+	) -> Vec<CiphertextRecordOf<T>> {
+		let to = min(to, from.saturating_add((MAX_RELEASE_RANGE - 1).into()));
+		let mut keys = Vec::new();
+		let mut height = from;
+		while height <= to {
+			keys.extend(
+				Ciphertexts::<T>::iter_key_prefix(height)
+					.filter(|id| !Plaintexts::<T>::contains_key(id))
+					.map(|id| (height, id)),
+			);
+			if height == to {
+				break
+			}
+			height = height.saturating_add(One::one());
+		}
+		Self::page(keys, offset, limit)
+	}
+
+	/// Sort the keys of the registry and read a page of the ciphertexts.
+	fn page(
+		mut keys: Vec<(T::BlockNumber, CipherId)>,
+		offset: u32,
+		limit: u32,
+	) -> Vec<CiphertextRecordOf<T>> {
+		keys.sort();
+		keys.into_iter()
+			.skip(offset as usize)
+			.take(min(limit, MAX_PAGE_SIZE) as usize)
+			.filter_map(|(release_height, id)| {
+				let info = Ciphertexts::<T>::get(release_height, id)?;
+				let info = CipherInfo {
+					submitter: info.submitter,
+					deposit: info.deposit,
+					release_height: info.release_height,
+					capsule: info.capsule,
+					ciphertext: info.ciphertext.into_inner(),
+				};
+				Some(CiphertextRecord { id, info, plaintext: Plaintexts::<T>::get(id) })
+			})
+			.collect()
+	}
+}
+
+/// The keys released by the mined blocks, which the block authors provide to the runtime.
+pub trait ReleasedKeys<BlockNumber> {
+	/// The key released by the block at a height, `None` if it is not provided yet.
+	fn released_key(height: BlockNumber) -> Option<RawReleasedKey>;
+
+	/// Store the key released at a height, to set up the benchmarks of the pallets reading it.
+	#[cfg(feature = "runtime-benchmarks")]
+	fn insert_released_key(_height: BlockNumber, _key: RawReleasedKey) {}
+}
+
+impl<BlockNumber> ReleasedKeys<BlockNumber> for () {
+	fn released_key(_: BlockNumber) -> Option<RawReleasedKey> {
+		None
 	}
 }
@@ -0,0 +1,62 @@
+//! Storage migrations of the TREX pallet.
+
+use super::*;
+use codec::Decode;
+use frame_support::{
+	traits::{Get, GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+	weights::Weight,
+};
+use sp_runtime::traits::Zero;
+use sp_std::marker::PhantomData;
+
+pub mod v1 {
+	use super::*;
+
+	/// Runtime upgrade to the ciphertext registry, it is run by the `Executive` of the runtime.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			migrate::<T>()
+		}
+	}
+
+	/// Move the last submission of the single value `TREXStorage` into the ciphertext registry.
+	///
+	/// The legacy value is a whole envelope, so the entry is registered under the target height
+	/// of its key capsule without deposit, and only the encrypted payload following the version
+	/// byte and the capsule is kept as its ciphertext. An envelope encrypted to several keys can
+	/// not be opened with a single capsule, so it is dropped.
+	pub fn migrate<T: Config>() -> Weight {
+		let on_chain = Pallet::<T>::on_chain_storage_version();
+		if on_chain >= 1 {
+			return T::DbWeight::get().reads(1)
+		}
+
+		let mut weight = T::DbWeight::get().reads_writes(2, 2);
+		// a value which can not be decoded or has no single key capsule is dropped.
+		let legacy = TREXStorage::<T>::take()
+			.and_then(|raw| TREXData::<T>::decode(&mut &raw[..]).ok())
+			.and_then(|data| {
+				let (capsules, payload) = elgamal::split_envelope(&data.cipher_list)?;
+				let [capsule]: [KeyCapsule; 1] = capsules.try_into().ok()?;
+				// the payload is a suffix of the legacy value, so it is within the bound.
+				let ciphertext = CiphertextOf::<T>::try_from(payload.to_vec()).ok()?;
+				Some((data, capsule, ciphertext))
+			});
+		if let Some((data, capsule, ciphertext)) = legacy {
+			let info = CipherInfo {
+				submitter: data.from,
+				deposit: Zero::zero(),
+				release_height: capsule.target.into(),
+				capsule,
+				ciphertext,
+			};
+			if Pallet::<T>::insert_cipher(info).is_ok() {
+				weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 3));
+			}
+		}
+		StorageVersion::new(1).put::<Pallet<T>>();
+		weight
+	}
+}
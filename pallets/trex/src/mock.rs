@@ -1,14 +1,17 @@
-use crate as pallet_difficulty;
-use frame_support::parameter_types;
+use crate as pallet_trex;
+use crate::{RawReleasedKey, ReleasedKeys};
+use frame_support::{parameter_types, traits::Everything};
 use frame_system as system;
 use sp_core::H256;
 use sp_runtime::{
-	testing::Header,
+	testing::{Header, TestXt},
 	traits::{BlakeTwo256, IdentityLookup},
 };
+use std::{cell::RefCell, collections::BTreeMap};
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
+type Balance = u64;
 
 // Configure a mock runtime to test the pallet.
 frame_support::construct_runtime!(
@@ -18,7 +21,8 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
-		DifficultyModule: pallet_difficulty::{Pallet, Call, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		TREXModule: pallet_trex::{Pallet, Call, Storage, Event<T>, ValidateUnsigned},
 	}
 );
 
@@ -28,7 +32,7 @@ parameter_types! {
 }
 
 impl system::Config for Test {
-	type BaseCallFilter = frame_support::traits::Everything;
+	type BaseCallFilter = Everything;
 	type BlockWeights = ();
 	type BlockLength = ();
 	type DbWeight = ();
@@ -45,19 +49,96 @@ impl system::Config for Test {
 	type BlockHashCount = BlockHashCount;
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<Balance>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
 	type SS58Prefix = SS58Prefix;
 	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
-impl pallet_difficulty::Config for Test {
+parameter_types! {
+	pub const ExistentialDeposit: Balance = 1;
+	pub const MaxLocks: u32 = 50;
+	pub const MaxReserves: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type Balance = Balance;
+	type DustRemoval = ();
 	type Event = Event;
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type MaxLocks = MaxLocks;
+	type MaxReserves = MaxReserves;
+	type ReserveIdentifier = [u8; 8];
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const MaxCipherLen: u32 = 256;
+	pub const DepositPerItem: Balance = 10;
+	pub const DepositPerByte: Balance = 1;
+	pub const RetentionPeriod: u64 = 5;
+	pub const MaxExpiredPerBlock: u32 = 3;
+	pub const UnsignedPriority: u64 = 1 << 20;
+}
+
+thread_local! {
+	/// The keys stored by the inherents of the blocks, by the height of the released block.
+	pub static RELEASED_KEYS: RefCell<BTreeMap<u64, RawReleasedKey>> = RefCell::new(BTreeMap::new());
+}
+
+pub struct MockReleasedKeys;
+
+impl MockReleasedKeys {
+	pub fn set(height: u64, key: RawReleasedKey) {
+		RELEASED_KEYS.with(|keys| keys.borrow_mut().insert(height, key));
+	}
+}
+
+impl ReleasedKeys<u64> for MockReleasedKeys {
+	fn released_key(height: u64) -> Option<RawReleasedKey> {
+		RELEASED_KEYS.with(|keys| keys.borrow().get(&height).cloned())
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn insert_released_key(height: u64, key: RawReleasedKey) {
+		Self::set(height, key)
+	}
+}
+
+impl pallet_trex::Config for Test {
+	type Event = Event;
+	type MaxCipherLen = MaxCipherLen;
+	type TREXWeight = ();
+	type Currency = Balances;
+	type DepositPerItem = DepositPerItem;
+	type DepositPerByte = DepositPerByte;
+	type RetentionPeriod = RetentionPeriod;
+	type MaxExpiredPerBlock = MaxExpiredPerBlock;
+	type UnsignedPriority = UnsignedPriority;
+	type ReleasedKeys = MockReleasedKeys;
+}
+
+impl<C> system::offchain::SendTransactionTypes<C> for Test
+where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = TestXt<Call, ()>;
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 100), (2, 100), (3, 12), (4, 10_000)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
 }
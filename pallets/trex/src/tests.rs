@@ -1,20 +1,392 @@
-use crate::{mock::*, Error};
-use frame_support::{assert_noop, assert_ok};
+use crate::{elgamal::KeyCapsule, mock::*, *};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::DispatchResult,
+	traits::{GetStorageVersion, Hooks, OnRuntimeUpgrade, ReservableCurrency, StorageVersion},
+	unsigned::ValidateUnsigned,
+	weights::Weight,
+};
+use sp_runtime::transaction_validity::TransactionSource;
+
+fn bounded(ciphertext: Vec<u8>) -> CiphertextOf<Test> {
+	ciphertext.try_into().unwrap()
+}
+
+/// A key over a 48 bit safe prime for testing, which is the minimum difficulty.
+fn test_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(1234567u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+/// Encrypt a secret to the test key, with an ephemeral key `k`.
+fn test_capsule(target: u32, secret: u64, k: u64) -> KeyCapsule {
+	elgamal::encrypt(&test_key(), target, secret.into(), k.into())
+}
+
+/// Send a payload released at the target height.
+fn send(who: u64, target: u32, payload: Vec<u8>) -> DispatchResult {
+	TREXModule::send_trex_data(Origin::signed(who), test_capsule(target, 42, 987654), payload)
+}
+
+/// Encode a capsule as the first version of envelope with an empty payload.
+fn test_envelope(capsule: &KeyCapsule) -> Vec<u8> {
+	(1u8, capsule, [0u8; 12], Vec::<u8>::new()).encode()
+}
+
+#[test]
+fn send_trex_data_registers_ciphertext() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(send(1, 10, vec![1, 2, 3]));
+		assert_ok!(send(2, 10, vec![4, 5]));
+		assert_ok!(send(1, 20, vec![6]));
+
+		assert_eq!(TREXModule::next_cipher_id(), 3);
+		assert_eq!(
+			TREXModule::ciphertexts(10, 1),
+			Some(CipherInfo {
+				submitter: 2,
+				deposit: 12,
+				release_height: 10,
+				capsule: test_capsule(10, 42, 987654),
+				ciphertext: bounded(vec![4, 5]),
+			})
+		);
+		assert_eq!(Ciphertexts::<Test>::iter_prefix(10).count(), 2);
+		assert_eq!(Ciphertexts::<Test>::iter_prefix(20).count(), 1);
+
+		let mut sent: Vec<_> = CiphertextsBySender::<Test>::iter_prefix(1).collect();
+		sent.sort();
+		assert_eq!(sent, vec![(0, 10), (2, 20)]);
+		System::assert_last_event(mock::Event::TREXModule(crate::Event::TREXDataSent(1, 2, 20)));
+	});
+}
+
+#[test]
+fn send_trex_data_reserves_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(TREXModule::deposit_of(3), 13);
+		assert_ok!(send(1, 10, vec![1, 2, 3]));
+		assert_ok!(send(1, 20, vec![4]));
+		assert_eq!(Balances::reserved_balance(1), 24);
+		assert_eq!(Balances::free_balance(1), 76);
+	});
+}
+
+#[test]
+fn send_trex_data_fails_without_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(send(3, 10, vec![1, 2, 3]), Error::<Test>::InsufficientDeposit);
+		assert_eq!(TREXModule::next_cipher_id(), 0);
+	});
+}
+
+#[test]
+fn send_trex_data_rejects_oversized_ciphertext() {
+	new_test_ext().execute_with(|| {
+		let max = <Test as Config>::MaxCipherLen::get() as usize;
+		assert_ok!(send(4, 10, vec![0; max]));
+		assert_noop!(send(4, 10, vec![0; max + 1]), Error::<Test>::CiphertextTooLong);
+	});
+}
+
+#[test]
+fn send_trex_data_validates_capsule() {
+	new_test_ext().execute_with(|| {
+		// the key of the current block is already released.
+		assert_noop!(send(1, 1, vec![0]), Error::<Test>::TargetNotInFuture);
+		assert_noop!(send(1, 0, vec![0]), Error::<Test>::TargetNotInFuture);
+
+		let capsule = test_capsule(10, 42, 987654);
+		for bit_length in [47, 225] {
+			assert_noop!(
+				TREXModule::send_trex_data(
+					Origin::signed(1),
+					KeyCapsule { bit_length, ..capsule.clone() },
+					vec![0]
+				),
+				Error::<Test>::InvalidBitLength
+			);
+		}
+		let invalid = [
+			KeyCapsule { c1: capsule.p, ..capsule.clone() },
+			KeyCapsule { c2: capsule.p + 1, ..capsule.clone() },
+			KeyCapsule { p: U256::one(), c1: U256::zero(), c2: U256::zero(), ..capsule.clone() },
+		];
+		for capsule in invalid {
+			assert_noop!(
+				TREXModule::send_trex_data(Origin::signed(1), capsule, vec![0]),
+				Error::<Test>::InvalidCapsule
+			);
+		}
+		assert_eq!(TREXModule::next_cipher_id(), 0);
+	});
+}
+
+#[test]
+fn withdraw_ciphertext_releases_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(send(1, 10, vec![1, 2, 3]));
+		assert_eq!(Balances::reserved_balance(1), 13);
+
+		// only the submitter can withdraw the ciphertext.
+		assert_noop!(
+			TREXModule::withdraw_ciphertext(Origin::signed(2), 0),
+			Error::<Test>::CiphertextNotFound
+		);
+		assert_ok!(TREXModule::withdraw_ciphertext(Origin::signed(1), 0));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 100);
+		assert_eq!(TREXModule::ciphertexts(10, 0), None);
+		assert_eq!(TREXModule::ciphertexts_by_sender(1, 0), None);
+		System::assert_last_event(mock::Event::TREXModule(crate::Event::CiphertextWithdrawn(
+			1, 0, 13,
+		)));
+
+		assert_noop!(
+			TREXModule::withdraw_ciphertext(Origin::signed(1), 0),
+			Error::<Test>::CiphertextNotFound
+		);
+	});
+}
 
 #[test]
-fn it_works_for_default_value() {
+fn expired_ciphertexts_are_swept() {
 	new_test_ext().execute_with(|| {
-		// Dispatch a signed extrinsic.
-		assert_ok!(DifficultyModule::do_something(Origin::signed(1), 42));
-		// Read pallet storage and assert an expected result.
-		assert_eq!(DifficultyModule::something(), Some(42));
+		System::set_block_number(0);
+		for (who, height) in [(1, 1), (1, 1), (2, 1), (2, 2), (1, 8)] {
+			assert_ok!(send(who, height, vec![0]));
+		}
+		assert_eq!(Balances::reserved_balance(1), 33);
+
+		// the sweep skips the empty height 0 and removes two ciphertexts of height 1.
+		TREXModule::on_idle(7, Weight::MAX);
+		assert_eq!(TREXModule::expiry_cursor(), 1);
+		assert_eq!(Ciphertexts::<Test>::iter_prefix(1).count(), 1);
+		let expired = System::events()
+			.into_iter()
+			.filter(|record| {
+				matches!(
+					record.event,
+					mock::Event::TREXModule(crate::Event::CiphertextExpired(_, _, 1))
+				)
+			})
+			.count();
+		assert_eq!(expired, 2);
+
+		// height 2 is still within its retention period.
+		TREXModule::on_idle(7, Weight::MAX);
+		assert_eq!(TREXModule::expiry_cursor(), 2);
+		assert_eq!(Ciphertexts::<Test>::iter_prefix(1).count(), 0);
+		assert_eq!(TREXModule::ciphertexts(2, 3).map(|info| info.submitter), Some(2));
+
+		TREXModule::on_idle(8, Weight::MAX);
+		assert_eq!(TREXModule::expiry_cursor(), 3);
+		assert_eq!(TREXModule::ciphertexts(2, 3), None);
+		assert_eq!(TREXModule::ciphertexts_by_sender(2, 3), None);
+
+		// only the deposit of the unexpired ciphertext is still reserved.
+		assert_eq!(Balances::reserved_balance(1), 11);
+		assert_eq!(Balances::reserved_balance(2), 0);
 	});
 }
 
 #[test]
-fn correct_error_for_none_value() {
+fn sweep_is_bounded_by_remaining_weight() {
 	new_test_ext().execute_with(|| {
-		// Ensure the expected error is thrown when no value is present.
-		assert_noop!(DifficultyModule::cause_error(Origin::signed(1)), Error::<Test>::NoneValue);
+		assert_ok!(send(1, 2, vec![0]));
+		assert_eq!(TREXModule::on_idle(10, 0), 0);
+		assert_eq!(TREXModule::expiry_cursor(), 0);
+		assert!(TREXModule::ciphertexts(2, 0).is_some());
+	});
+}
+
+#[test]
+fn elgamal_decrypt_and_verify() {
+	let key = test_key();
+	assert!(elgamal::verify_key(&key));
+	assert!(!elgamal::verify_key(&RawReleasedKey { x: key.x + 1, ..key.clone() }));
+
+	let capsule = test_capsule(1, 42, 987654);
+	assert_eq!(elgamal::capsules(&test_envelope(&capsule)), vec![capsule.clone()]);
+	assert_eq!(elgamal::decrypt(&key, &capsule), Some(U256::from(42)));
+	assert!(elgamal::verify(&key, &capsule, U256::from(42)));
+	assert!(!elgamal::verify(&key, &capsule, U256::from(43)));
+	assert!(elgamal::capsules(&[3u8, 1, 2]).is_empty());
+}
+
+#[test]
+fn publish_plaintexts_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(send(4, 2, vec![]));
+		MockReleasedKeys::set(2, test_key());
+		let call = crate::Call::publish_plaintexts {
+			release_height: 2,
+			plaintexts: vec![(0, U256::from(42))],
+		};
+		assert!(TREXModule::validate_unsigned(TransactionSource::Local, &call).is_ok());
+
+		assert_ok!(TREXModule::publish_plaintexts(Origin::none(), 2, vec![(0, U256::from(42))]));
+		assert_eq!(TREXModule::plaintexts(0), Some(U256::from(42)));
+		System::assert_last_event(mock::Event::TREXModule(crate::Event::PlaintextPublished(0, 2)));
+		assert!(TREXModule::validate_unsigned(TransactionSource::Local, &call).is_err());
+
+		// the plaintext is removed along with its ciphertext.
+		assert_ok!(TREXModule::withdraw_ciphertext(Origin::signed(4), 0));
+		assert_eq!(TREXModule::plaintexts(0), None);
+	});
+}
+
+#[test]
+fn publish_plaintexts_rejects_invalid() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(send(4, 2, vec![]));
+		assert_ok!(send(4, 3, vec![]));
+		MockReleasedKeys::set(2, test_key());
+
+		assert_noop!(
+			TREXModule::publish_plaintexts(Origin::none(), 2, vec![(0, U256::from(7))]),
+			Error::<Test>::InvalidPlaintext
+		);
+		assert_noop!(
+			TREXModule::publish_plaintexts(Origin::none(), 3, vec![(1, U256::from(42))]),
+			Error::<Test>::KeyNotReleased
+		);
+		assert_noop!(
+			TREXModule::publish_plaintexts(Origin::signed(4), 2, vec![(0, U256::from(42))]),
+			sp_runtime::traits::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn publish_plaintexts_rejects_forged_key() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(send(4, 2, vec![]));
+		MockReleasedKeys::set(2, test_key());
+
+		// a forged key with its own public key is consistent, and so is the secret it decrypts.
+		let forged = RawReleasedKey { x: U256::from(7654321u64), ..test_key() };
+		let forged = RawReleasedKey { h: elgamal::pow_mod(forged.g, forged.x, forged.p), ..forged };
+		assert!(elgamal::verify_key(&forged));
+		let capsule = TREXModule::ciphertexts(2, 0).unwrap().capsule;
+		let secret = elgamal::decrypt(&forged, &capsule).unwrap();
+		assert_ne!(secret, U256::from(42));
+		assert!(elgamal::verify(&forged, &capsule, secret));
+
+		// but the secret is checked with the key stored on chain.
+		assert_noop!(
+			TREXModule::publish_plaintexts(Origin::none(), 2, vec![(0, secret)]),
+			Error::<Test>::InvalidPlaintext
+		);
+		assert_ok!(TREXModule::publish_plaintexts(Origin::none(), 2, vec![(0, U256::from(42))]));
+	});
+}
+
+#[test]
+fn query_ciphertexts_with_pagination() {
+	new_test_ext().execute_with(|| {
+		for (who, height) in [(1, 3), (2, 3), (1, 2), (1, 3), (2, 5)] {
+			assert_ok!(send(who, height, vec![who as u8]));
+		}
+		let ids = |records: Vec<CiphertextRecordOf<Test>>| -> Vec<CipherId> {
+			records.into_iter().map(|record| record.id).collect()
+		};
+
+		assert_eq!(ids(TREXModule::ciphertexts_by_release(3, 0, 10)), vec![0, 1, 3]);
+		assert_eq!(ids(TREXModule::ciphertexts_by_release(3, 1, 1)), vec![1]);
+		assert_eq!(ids(TREXModule::ciphertexts_by_release(3, 3, 10)), vec![]);
+		assert_eq!(ids(TREXModule::ciphertexts_by_sender(1, 0, 10)), vec![0, 2, 3]);
+		assert_eq!(ids(TREXModule::ciphertexts_by_sender(2, 1, 10)), vec![4]);
+
+		let record = TREXModule::ciphertexts_by_release(2, 0, 10).pop().unwrap();
+		assert_eq!(record.info.submitter, 1);
+		assert_eq!(record.info.capsule, test_capsule(2, 42, 987654));
+		assert_eq!(record.info.ciphertext, vec![1]);
+		assert_eq!(record.plaintext, None);
+
+		// the pending releases are ordered by height and exclude the published plaintexts.
+		assert_eq!(ids(TREXModule::pending_releases(2, 5, 0, 10)), vec![2, 0, 1, 3, 4]);
+		assert_eq!(ids(TREXModule::pending_releases(3, 4, 1, 2)), vec![1, 3]);
+		MockReleasedKeys::set(3, test_key());
+		assert_ok!(TREXModule::publish_plaintexts(Origin::none(), 3, vec![(1, U256::from(42))]));
+		assert_eq!(ids(TREXModule::pending_releases(0, 10, 0, 10)), vec![2, 0, 3, 4]);
+		// the range of heights is capped.
+		assert_ok!(send(2, MAX_RELEASE_RANGE + 3, vec![]));
+		assert_eq!(ids(TREXModule::pending_releases(3, u64::MAX, 0, 10)), vec![0, 3, 4]);
+		assert_eq!(ids(TREXModule::pending_releases(4, u64::MAX, 0, 10)), vec![4, 5]);
+		let published = TREXModule::ciphertexts_by_sender(2, 0, 1).pop().unwrap();
+		assert_eq!(published.plaintext, Some(U256::from(42)));
+	});
+}
+
+#[test]
+fn query_page_size_is_capped() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..MAX_PAGE_SIZE + 1 {
+			assert_ok!(send(4, 2, vec![]));
+		}
+		assert_eq!(
+			TREXModule::ciphertexts_by_release(2, 0, u32::MAX).len(),
+			MAX_PAGE_SIZE as usize
+		);
+		assert_eq!(TREXModule::ciphertexts_by_sender(4, MAX_PAGE_SIZE, u32::MAX).len(), 1);
+	});
+}
+
+#[test]
+fn migrate_legacy_storage() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<TREXModule>();
+		let capsule = test_capsule(5, 42, 987654);
+		let payload = ([7u8; 12], vec![1u8, 2, 3]).encode();
+		let envelope = [&(1u8, &capsule).encode()[..], &payload].concat();
+		let legacy = TREXData::<Test> { cipher_list: bounded(envelope), from: 3 };
+		TREXStorage::<Test>::put(legacy.encode());
+
+		migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+		assert_eq!(TREXStorage::<Test>::get(), None);
+		assert_eq!(
+			TREXModule::ciphertexts(5, 0),
+			Some(CipherInfo {
+				submitter: 3,
+				deposit: 0,
+				release_height: 5,
+				capsule,
+				ciphertext: bounded(payload),
+			})
+		);
+		assert_eq!(TREXModule::ciphertexts_by_sender(3, 0), Some(5));
+		assert_eq!(TREXModule::on_chain_storage_version(), 1);
+	});
+}
+
+#[test]
+fn migrate_drops_legacy_storage_without_capsule() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<TREXModule>();
+		let legacy = TREXData::<Test> { cipher_list: bounded(vec![7, 8, 9]), from: 3 };
+		TREXStorage::<Test>::put(legacy.encode());
+
+		migrations::v1::migrate::<Test>();
+
+		assert_eq!(TREXStorage::<Test>::get(), None);
+		assert_eq!(TREXModule::next_cipher_id(), 0);
+
+		// an envelope of several keys can not be registered with a single capsule.
+		StorageVersion::new(0).put::<TREXModule>();
+		let capsules = vec![test_capsule(5, 42, 987654), test_capsule(6, 42, 987654)];
+		let envelope = (2u8, capsules, [0u8; 12], Vec::<u8>::new()).encode();
+		let legacy = TREXData::<Test> { cipher_list: bounded(envelope), from: 3 };
+		TREXStorage::<Test>::put(legacy.encode());
+
+		migrations::v1::migrate::<Test>();
+
+		assert_eq!(TREXStorage::<Test>::get(), None);
+		assert_eq!(TREXModule::next_cipher_id(), 0);
+		assert_eq!(TREXModule::on_chain_storage_version(), 1);
 	});
 }
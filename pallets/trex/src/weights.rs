@@ -15,26 +15,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Autogenerated weights for pallet_balances
+//! Weights for pallet_trex
 //!
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 4.0.0-dev
-//! DATE: 2021-09-13, STEPS: `50`, REPEAT: 20, LOW RANGE: `[]`, HIGH RANGE: `[]`
-//! EXECUTION: Some(Wasm), WASM-EXECUTION: Compiled, CHAIN: Some("dev"), DB CACHE: 128
-
-// Executed Command:
-// target/release/trex-node
-// benchmark
-// --chain=dev
-// --steps=50
-// --repeat=20
-// --pallet=predict_transaction
-// --extrinsic=*
-// --execution=wasm
-// --wasm-execution=compiled
-// --heap-pages=4096
-// --output=./trex/src/weights_info
-// --difficulty=./.maintain/frame-weight-difficulty.hbs
-
+//! The weights are estimated from the storage accesses of the calls and the cost of the modular
+//! arithmetic on 48 bit keys, they are not measured yet. Replace them with the output of the
+//! benchmarking CLI for `pallet_trex` on the reference hardware.
 
 #![cfg_attr(rustfmt, rustfmt_skip)]
 #![allow(unused_parens)]
@@ -43,28 +28,81 @@
 use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
 use sp_std::marker::PhantomData;
 
-/// Weight functions needed for pallet_balances.
+/// Weight functions needed for pallet_trex.
 pub trait TREXWeight {
-    fn send_trex_data() -> Weight;
+    fn send_trex_data(l: u32, ) -> Weight;
+    fn withdraw_ciphertext() -> Weight;
+    fn expire_ciphertext() -> Weight;
+    fn publish_plaintexts(n: u32) -> Weight;
 }
 
-/// Weights for pallet_balances using the Substrate node and recommended hardware.
+/// Weights for pallet_trex using the Substrate node and recommended hardware.
 pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> TREXWeight for SubstrateWeight<T> {
+    // Storage: TREXModule NextCipherId (r:1 w:1), Ciphertexts (r:0 w:1), CiphertextsBySender (r:0 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn send_trex_data(l: u32, ) -> Weight {
+        (52_317_000 as Weight)
+            .saturating_add((2_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(4 as Weight))
+    }
+    // Storage: TREXModule CiphertextsBySender (r:1 w:1), Ciphertexts (r:1 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn withdraw_ciphertext() -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(3 as Weight))
+            .saturating_add(T::DbWeight::get().writes(3 as Weight))
+    }
+    // Storage: TREXModule Ciphertexts (r:1 w:1), CiphertextsBySender (r:0 w:1)
     // Storage: System Account (r:1 w:1)
-    fn send_trex_data() -> Weight {
-        (70_952_000 as Weight)
+    fn expire_ciphertext() -> Weight {
+        (30_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(3 as Weight))
+    }
+    // Storage: TREXKeys Released (r:1 w:0)
+    // Storage: TREXModule Ciphertexts (r:1 w:0), Plaintexts (r:1 w:1)
+    fn publish_plaintexts(n: u32, ) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
             .saturating_add(T::DbWeight::get().reads(1 as Weight))
-            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+            .saturating_add(T::DbWeight::get().reads((2 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(T::DbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
     }
 }
 
 // For backwards compatibility and tests
 impl TREXWeight for () {
+    // Storage: TREXModule NextCipherId (r:1 w:1), Ciphertexts (r:0 w:1), CiphertextsBySender (r:0 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn send_trex_data(l: u32, ) -> Weight {
+        (52_317_000 as Weight)
+            .saturating_add((2_000 as Weight).saturating_mul(l as Weight))
+            .saturating_add(RocksDbWeight::get().reads(2 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(4 as Weight))
+    }
+    // Storage: TREXModule CiphertextsBySender (r:1 w:1), Ciphertexts (r:1 w:1)
     // Storage: System Account (r:1 w:1)
-    fn send_trex_data() -> Weight {
-        (70_952_000 as Weight)
+    fn withdraw_ciphertext() -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(3 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(3 as Weight))
+    }
+    // Storage: TREXModule Ciphertexts (r:1 w:1), CiphertextsBySender (r:0 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn expire_ciphertext() -> Weight {
+        (30_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(2 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(3 as Weight))
+    }
+    // Storage: TREXKeys Released (r:1 w:0)
+    // Storage: TREXModule Ciphertexts (r:1 w:0), Plaintexts (r:1 w:1)
+    fn publish_plaintexts(n: u32, ) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((25_000_000 as Weight).saturating_mul(n as Weight))
             .saturating_add(RocksDbWeight::get().reads(1 as Weight))
-            .saturating_add(RocksDbWeight::get().writes(1 as Weight))
+            .saturating_add(RocksDbWeight::get().reads((2 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(RocksDbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
     }
 }
\ No newline at end of file
@@ -0,0 +1,105 @@
+//! Benchmarking setup for pallet-trex-voting
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Voting;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+use sp_runtime::traits::Bounded;
+
+/// The end block of the benchmarked referenda.
+const END: u32 = 10;
+
+/// A key over a 48 bit safe prime, the cost of the arithmetic is dominated by the big integers.
+fn bench_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(98765432109u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+/// A ballot of an option to the key of the end block.
+fn ballot(key: &RawReleasedKey, choice: u32) -> KeyCapsule {
+	elgamal::encrypt(key, END, choice.into(), U256::from(123456u32 + choice))
+}
+
+fn funded<T: Config>(who: &T::AccountId) {
+	T::Currency::make_free_balance_be(who, BalanceOf::<T>::max_value() / 2u32.into());
+}
+
+/// Start a referendum of `choices` options ending at `END` with `n` ballots to a key, returns the
+/// voters and their options.
+fn referendum_with_ballots<T: Config>(
+	key: &RawReleasedKey,
+	choices: u32,
+	n: u32,
+) -> Vec<(T::AccountId, U256)> {
+	frame_system::Pallet::<T>::set_block_number(1u32.into());
+	let origin = T::ReferendumOrigin::successful_origin();
+	let end: T::BlockNumber = END.into();
+	Voting::<T>::start_referendum(origin, end, choices).expect("the origin is successful");
+	(0..n)
+		.map(|i| {
+			let voter: T::AccountId = account("voter", i, 0);
+			funded::<T>(&voter);
+			let choice = i % choices + 1;
+			Voting::<T>::vote(RawOrigin::Signed(voter.clone()).into(), 0, ballot(key, choice))
+				.expect("the voter is funded");
+			(voter, U256::from(choice))
+		})
+		.collect()
+}
+
+/// Mine the block after the end block, which stores the key released by the end block.
+fn mine_after_end<T: Config>() {
+	frame_system::Pallet::<T>::set_block_number((END + 1).into());
+	T::ReleasedKeys::insert_released_key(END.into(), bench_key());
+}
+
+benchmarks! {
+	start_referendum {
+		let origin = T::ReferendumOrigin::successful_origin();
+		let end: T::BlockNumber = END.into();
+	}: _<T::Origin>(origin, end, T::MaxChoices::get())
+	verify {
+		assert!(Referenda::<T>::contains_key(0));
+	}
+
+	vote {
+		referendum_with_ballots::<T>(&bench_key(), T::MaxChoices::get(), 0);
+		let caller: T::AccountId = whitelisted_caller();
+		funded::<T>(&caller);
+	}: _(RawOrigin::Signed(caller.clone()), 0, ballot(&bench_key(), 1))
+	verify {
+		assert!(Ballots::<T>::contains_key(0, &caller));
+	}
+
+	// The worst case opens the last ballots and announces the counts of every option.
+	tally {
+		let n in 1 .. T::MaxBallots::get();
+		let c in 1 .. T::MaxChoices::get();
+		let votes = referendum_with_ballots::<T>(&bench_key(), c, n);
+		mine_after_end::<T>();
+		let caller: T::AccountId = whitelisted_caller();
+	}: _(RawOrigin::Signed(caller), 0, votes)
+	verify {
+		assert!(Voting::<T>::results_of(0).is_some());
+	}
+
+	reject_ballots {
+		let n in 1 .. T::MaxBallots::get();
+		let other = RawReleasedKey { p: bench_key().p - 2, ..bench_key() };
+		let voters = referendum_with_ballots::<T>(&other, T::MaxChoices::get(), n)
+			.into_iter()
+			.map(|(voter, _)| voter)
+			.collect::<Vec<_>>();
+		mine_after_end::<T>();
+		let caller: T::AccountId = whitelisted_caller();
+	}: _(RawOrigin::Signed(caller), 0, voters)
+	verify {
+		assert!(Voting::<T>::results_of(0).is_some());
+	}
+
+	impl_benchmark_test_suite!(Voting, crate::mock::new_test_ext(), crate::mock::Test);
+}
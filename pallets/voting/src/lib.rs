@@ -0,0 +1,394 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Secret ballots built on the timed release encryption of TREX.
+//!
+//! A ballot is an ElGamal key capsule of the chosen option encrypted to the public key mined at
+//! the end block of a referendum, so no ballot can be read, and no partial result is known, before
+//! the voting ends. Once the end block is mined, its private key is released by the seal and stored
+//! on chain by the next block, then anyone can open the ballots, every opening is checked with the
+//! stored key before it is counted. A voter reserves a bond until its ballot is opened, so the
+//! ballots of a referendum can not be filled by free accounts.
+use frame_support::{
+	ensure,
+	traits::{Currency, Get, Imbalance, ReservableCurrency},
+};
+pub use pallet::*;
+pub use pallet_trex::{elgamal, KeyCapsule, RawReleasedKey, ReleasedKeys};
+use sp_core::U256;
+use sp_std::{collections::btree_set::BTreeSet, vec::Vec};
+pub mod weights;
+pub use weights::VotingWeight;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	/// Identifier of a referendum.
+	pub type ReferendumIndex = u32;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin which can start a referendum.
+		type ReferendumOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Maximum number of options of a referendum.
+		#[pallet::constant]
+		type MaxChoices: Get<u32>;
+
+		/// Maximum number of ballots of a referendum.
+		#[pallet::constant]
+		type MaxBallots: Get<u32>;
+
+		/// The currency to reserve the bonds of the voters.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// The bond reserved from a voter until its ballot is opened, which is slashed if the
+		/// ballot is not encrypted to the released key.
+		#[pallet::constant]
+		type VotingBond: Get<BalanceOf<Self>>;
+
+		/// The keys released by the mined blocks, which open the ballots.
+		type ReleasedKeys: ReleasedKeys<Self::BlockNumber>;
+
+		/// Weight information for extrinsics in this pallet.
+		type VotingWeight: VotingWeight;
+	}
+
+	/// Type alias for currency balance.
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	/// A referendum with its progress of the tally.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct ReferendumInfo<BlockNumber> {
+		/// The block whose key encrypts the ballots, no ballot is accepted from this block.
+		pub end: BlockNumber,
+		/// Number of the options, which are numbered from 1.
+		pub choices: u32,
+		/// Number of the ballots cast.
+		pub ballots: u32,
+		/// Number of the ballots opened or rejected.
+		pub opened: u32,
+		/// Whether every ballot is opened or rejected and the result is announced.
+		pub tallied: bool,
+	}
+
+	/// The state of a ballot.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub enum BallotState {
+		/// The ballot is not opened yet.
+		Sealed,
+		/// The ballot is opened and counted for the option.
+		Counted(u32),
+		/// The ballot is opened but it is not a valid option.
+		Spoiled,
+		/// The ballot is not encrypted to the released key, so it can never be opened.
+		Rejected,
+	}
+
+	/// A secret ballot of a referendum.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct BallotInfo<Balance> {
+		/// The chosen option encrypted to the key of the end block.
+		pub capsule: KeyCapsule,
+		/// Bond reserved from the voter.
+		pub bond: Balance,
+		pub state: BallotState,
+	}
+
+	/// The index of the next referendum.
+	#[pallet::storage]
+	#[pallet::getter(fn referendum_count)]
+	pub type ReferendumCount<T> = StorageValue<_, ReferendumIndex, ValueQuery>;
+
+	/// Information of the referenda.
+	#[pallet::storage]
+	#[pallet::getter(fn referenda)]
+	pub type Referenda<T: Config> =
+		StorageMap<_, Twox64Concat, ReferendumIndex, ReferendumInfo<T::BlockNumber>>;
+
+	/// The ballot of each voter in a referendum, an account can only cast one ballot.
+	#[pallet::storage]
+	#[pallet::getter(fn ballots)]
+	pub type Ballots<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		ReferendumIndex,
+		Blake2_128Concat,
+		T::AccountId,
+		BallotInfo<BalanceOf<T>>,
+	>;
+
+	/// Number of the counted ballots of each option of a referendum.
+	#[pallet::storage]
+	#[pallet::getter(fn tally_count)]
+	pub type Tally<T> =
+		StorageDoubleMap<_, Twox64Concat, ReferendumIndex, Twox64Concat, u32, u32, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A referendum is started. \[ref_index, end, choices\]
+		ReferendumStarted(ReferendumIndex, T::BlockNumber, u32),
+		/// A secret ballot is cast. \[ref_index, voter\]
+		Voted(ReferendumIndex, T::AccountId),
+		/// A ballot is opened with the released key, with its option if it is valid.
+		/// \[ref_index, voter, choice\]
+		BallotOpened(ReferendumIndex, T::AccountId, Option<u32>),
+		/// A ballot which is not encrypted to the released key is rejected and its bond is
+		/// slashed. \[ref_index, voter, bond\]
+		BallotRejected(ReferendumIndex, T::AccountId, BalanceOf<T>),
+		/// All the ballots of a referendum are opened, with the counts of the options in order.
+		/// \[ref_index, tally\]
+		ReferendumTallied(ReferendumIndex, Vec<u32>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The end block of the referendum is not in the future.
+		EndNotInFuture,
+		/// The number of options is zero or above `MaxChoices`.
+		InvalidChoices,
+		/// No referendum is found with the index.
+		ReferendumNotFound,
+		/// The referendum does not accept ballots after its end block.
+		VotingClosed,
+		/// The ballot is not encrypted to the key of the end block.
+		InvalidTarget,
+		/// The key capsule of the ballot is malformed.
+		InvalidCapsule,
+		/// The account already voted in the referendum.
+		AlreadyVoted,
+		/// The referendum has reached `MaxBallots`.
+		TooManyBallots,
+		/// The voter can not afford the bond.
+		InsufficientBond,
+		/// The key of the end block is not released yet.
+		KeyNotReleased,
+		/// All the ballots of the referendum are already opened.
+		AlreadyTallied,
+		/// The ballot is not encrypted to the released key, it must be rejected instead.
+		KeyMismatch,
+		/// The ballot has a valid opening with the released key, it must be opened instead.
+		CanBeOpened,
+		/// No ballot of the voter is found in the referendum.
+		BallotNotFound,
+		/// The ballot is already opened.
+		AlreadyOpened,
+		/// The option is not the decryption of the ballot.
+		InvalidOpening,
+		/// Next referendum index overflows.
+		ReferendumIndexOverflow,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Start a referendum of `choices` options, which ends at a future block.
+		#[pallet::weight(T::VotingWeight::start_referendum())]
+		pub fn start_referendum(
+			origin: OriginFor<T>,
+			end: T::BlockNumber,
+			choices: u32,
+		) -> DispatchResult {
+			T::ReferendumOrigin::ensure_origin(origin)?;
+			ensure!(end > frame_system::Pallet::<T>::block_number(), Error::<T>::EndNotInFuture);
+			ensure!(choices > 0 && choices <= T::MaxChoices::get(), Error::<T>::InvalidChoices);
+
+			let index = ReferendumCount::<T>::get();
+			let next = index.checked_add(1).ok_or(Error::<T>::ReferendumIndexOverflow)?;
+			ReferendumCount::<T>::put(next);
+			let info = ReferendumInfo { end, choices, ballots: 0, opened: 0, tallied: false };
+			Referenda::<T>::insert(index, info);
+			Self::deposit_event(Event::ReferendumStarted(index, end, choices));
+			Ok(())
+		}
+
+		/// Cast a secret ballot, which is the chosen option encrypted to the key of the end block.
+		/// An account can only vote once in a referendum, and the `VotingBond` is reserved until
+		/// the ballot is opened.
+		#[pallet::weight(T::VotingWeight::vote())]
+		pub fn vote(
+			origin: OriginFor<T>,
+			ref_index: ReferendumIndex,
+			capsule: KeyCapsule,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut info = Referenda::<T>::get(ref_index).ok_or(Error::<T>::ReferendumNotFound)?;
+			ensure!(frame_system::Pallet::<T>::block_number() < info.end, Error::<T>::VotingClosed);
+			ensure!(T::BlockNumber::from(capsule.target) == info.end, Error::<T>::InvalidTarget);
+			// a ballot with `c1 = 0` can not be the encryption of any option.
+			ensure!(
+				capsule.has_valid_bit_length() && capsule.is_reduced() && !capsule.c1.is_zero(),
+				Error::<T>::InvalidCapsule
+			);
+			ensure!(!Ballots::<T>::contains_key(ref_index, &who), Error::<T>::AlreadyVoted);
+			ensure!(info.ballots < T::MaxBallots::get(), Error::<T>::TooManyBallots);
+			let bond = T::VotingBond::get();
+			T::Currency::reserve(&who, bond).map_err(|_| Error::<T>::InsufficientBond)?;
+
+			Ballots::<T>::insert(
+				ref_index,
+				&who,
+				BallotInfo { capsule, bond, state: BallotState::Sealed },
+			);
+			info.ballots += 1;
+			Referenda::<T>::insert(ref_index, info);
+			Self::deposit_event(Event::Voted(ref_index, who));
+			Ok(())
+		}
+
+		/// Open the ballots with the key stored for the end block, which can be dispatched by
+		/// anyone once the key is stored. The result is announced once every ballot is opened or
+		/// rejected, a referendum without ballots is tallied with an empty list of openings.
+		///
+		/// Each option is checked to be the decryption of its ballot, and the bond of its voter is
+		/// released. A ballot of an option out of range is spoiled, so its option is ignored.
+		#[pallet::weight(T::VotingWeight::tally(votes.len() as u32, T::MaxChoices::get()))]
+		pub fn tally(
+			origin: OriginFor<T>,
+			ref_index: ReferendumIndex,
+			votes: Vec<(T::AccountId, U256)>,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			let mut info = Referenda::<T>::get(ref_index).ok_or(Error::<T>::ReferendumNotFound)?;
+			ensure!(!info.tallied, Error::<T>::AlreadyTallied);
+			ensure!(votes.len() <= T::MaxBallots::get() as usize, Error::<T>::TooManyBallots);
+			let key = T::ReleasedKeys::released_key(info.end).ok_or(Error::<T>::KeyNotReleased)?;
+
+			// check all the openings before counting any of them.
+			let mut opened = Vec::with_capacity(votes.len());
+			let mut voters = BTreeSet::new();
+			for (who, secret) in votes {
+				ensure!(voters.insert(who.clone()), Error::<T>::AlreadyOpened);
+				let mut ballot =
+					Ballots::<T>::get(ref_index, &who).ok_or(Error::<T>::BallotNotFound)?;
+				ensure!(ballot.state == BallotState::Sealed, Error::<T>::AlreadyOpened);
+				ballot.state = Self::open(&key, &ballot.capsule, secret, info.choices)?;
+				opened.push((who, ballot));
+			}
+
+			for (who, ballot) in opened {
+				let choice = match ballot.state {
+					BallotState::Counted(choice) => Some(choice),
+					_ => None,
+				};
+				if let Some(choice) = choice {
+					Tally::<T>::mutate(ref_index, choice, |count| *count += 1);
+				}
+				T::Currency::unreserve(&who, ballot.bond);
+				Ballots::<T>::insert(ref_index, &who, ballot);
+				info.opened += 1;
+				Self::deposit_event(Event::BallotOpened(ref_index, who, choice));
+			}
+			Self::conclude(ref_index, info);
+			Ok(())
+		}
+
+		/// Reject the ballots which have no valid opening with the key stored for the end block,
+		/// e.g. the ballots encrypted to another key, which can be dispatched by anyone once the
+		/// key is stored. Such a ballot can never be opened, so it is not counted and the bond of
+		/// its voter is slashed.
+		#[pallet::weight(T::VotingWeight::reject_ballots(voters.len() as u32))]
+		pub fn reject_ballots(
+			origin: OriginFor<T>,
+			ref_index: ReferendumIndex,
+			voters: Vec<T::AccountId>,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			let mut info = Referenda::<T>::get(ref_index).ok_or(Error::<T>::ReferendumNotFound)?;
+			ensure!(!info.tallied, Error::<T>::AlreadyTallied);
+			ensure!(voters.len() <= T::MaxBallots::get() as usize, Error::<T>::TooManyBallots);
+			let key = T::ReleasedKeys::released_key(info.end).ok_or(Error::<T>::KeyNotReleased)?;
+
+			// check all the ballots before rejecting any of them.
+			let mut rejected = Vec::with_capacity(voters.len());
+			let mut unique = BTreeSet::new();
+			for who in voters {
+				ensure!(unique.insert(who.clone()), Error::<T>::AlreadyOpened);
+				let mut ballot =
+					Ballots::<T>::get(ref_index, &who).ok_or(Error::<T>::BallotNotFound)?;
+				ensure!(ballot.state == BallotState::Sealed, Error::<T>::AlreadyOpened);
+				ensure!(!Self::can_be_opened(&key, &ballot.capsule), Error::<T>::CanBeOpened);
+				ballot.state = BallotState::Rejected;
+				rejected.push((who, ballot));
+			}
+
+			for (who, ballot) in rejected {
+				let (slashed, _) = T::Currency::slash_reserved(&who, ballot.bond);
+				Ballots::<T>::insert(ref_index, &who, ballot);
+				info.opened += 1;
+				Self::deposit_event(Event::BallotRejected(ref_index, who, slashed.peek()));
+			}
+			Self::conclude(ref_index, info);
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Whether a ballot is encrypted to the released key, a ballot to another key can never be
+	/// opened.
+	fn matches(key: &RawReleasedKey, capsule: &KeyCapsule) -> bool {
+		capsule.p == key.p && capsule.bit_length == key.bit_length
+	}
+
+	/// Whether a ballot has a valid opening with the released key, which is its decryption if there
+	/// is any.
+	fn can_be_opened(key: &RawReleasedKey, capsule: &KeyCapsule) -> bool {
+		elgamal::decrypt(key, capsule).map_or(false, |secret| elgamal::verify(key, capsule, secret))
+	}
+
+	/// Check the opening of a ballot and return the state of the opened ballot.
+	fn open(
+		key: &RawReleasedKey,
+		capsule: &KeyCapsule,
+		secret: U256,
+		choices: u32,
+	) -> Result<BallotState, Error<T>> {
+		ensure!(Self::matches(key, capsule), Error::<T>::KeyMismatch);
+		ensure!(elgamal::verify(key, capsule, secret), Error::<T>::InvalidOpening);
+		let state = match u32::try_from(secret) {
+			Ok(choice) if (1..=choices).contains(&choice) => BallotState::Counted(choice),
+			_ => BallotState::Spoiled,
+		};
+		Ok(state)
+	}
+
+	/// Store the progress of a referendum, and announce the result once every ballot is opened
+	/// or rejected.
+	fn conclude(ref_index: ReferendumIndex, mut info: ReferendumInfo<T::BlockNumber>) {
+		info.tallied = info.opened == info.ballots;
+		let tally = info.tallied.then(|| Self::counts(ref_index, info.choices));
+		Referenda::<T>::insert(ref_index, info);
+		if let Some(tally) = tally {
+			Self::deposit_event(Event::ReferendumTallied(ref_index, tally));
+		}
+	}
+
+	/// The counts of the options of a referendum in order, once all its ballots are opened.
+	pub fn results_of(ref_index: ReferendumIndex) -> Option<Vec<u32>> {
+		let info = Referenda::<T>::get(ref_index)?;
+		info.tallied.then(|| Self::counts(ref_index, info.choices))
+	}
+
+	fn counts(ref_index: ReferendumIndex, choices: u32) -> Vec<u32> {
+		(1..=choices).map(|choice| Tally::<T>::get(ref_index, choice)).collect()
+	}
+}
@@ -0,0 +1,256 @@
+use crate::{mock::*, *};
+use frame_support::{assert_noop, assert_ok, error::BadOrigin};
+
+/// A key over a 48 bit safe prime, which stands for the key released by the end block.
+fn released_key() -> RawReleasedKey {
+	let p = U256::from(140737488356903u64);
+	let g = U256::from(5u64);
+	let x = U256::from(98765432109u64);
+	RawReleasedKey { bit_length: 48, p, g, h: elgamal::pow_mod(g, x, p), x }
+}
+
+/// A secret ballot of an option to the key of the end block.
+fn ballot(end: u32, choice: u64) -> KeyCapsule {
+	elgamal::encrypt(&released_key(), end, choice.into(), (123456 + choice).into())
+}
+
+/// Mine the block after the end block, which stores the key released by the end block.
+fn mine_after_end(end: u64) {
+	System::set_block_number(end + 1);
+	MockReleasedKeys::set(end, released_key());
+}
+
+fn openings(votes: Vec<(u64, u64)>) -> Vec<(u64, U256)> {
+	votes.into_iter().map(|(who, choice)| (who, choice.into())).collect()
+}
+
+#[test]
+fn start_referendum_works() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Voting::start_referendum(Origin::signed(1), 10, 2), BadOrigin);
+		assert_noop!(Voting::start_referendum(Origin::root(), 1, 2), Error::<Test>::EndNotInFuture);
+		for choices in [0, 4] {
+			assert_noop!(
+				Voting::start_referendum(Origin::root(), 10, choices),
+				Error::<Test>::InvalidChoices
+			);
+		}
+
+		assert_ok!(Voting::start_referendum(Origin::root(), 10, 2));
+		assert_eq!(Voting::referendum_count(), 1);
+		assert_eq!(
+			Voting::referenda(0),
+			Some(ReferendumInfo { end: 10, choices: 2, ballots: 0, opened: 0, tallied: false })
+		);
+		System::assert_last_event(mock::Event::Voting(crate::Event::ReferendumStarted(0, 10, 2)));
+	});
+}
+
+#[test]
+fn vote_checks_the_ballot() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Voting::start_referendum(Origin::root(), 10, 3));
+
+		assert_noop!(
+			Voting::vote(Origin::signed(1), 1, ballot(10, 1)),
+			Error::<Test>::ReferendumNotFound
+		);
+		assert_noop!(
+			Voting::vote(Origin::signed(1), 0, ballot(11, 1)),
+			Error::<Test>::InvalidTarget
+		);
+		let sealed = ballot(10, 1);
+		for capsule in [
+			KeyCapsule { bit_length: 40, ..sealed.clone() },
+			KeyCapsule { c2: sealed.p, ..sealed.clone() },
+			KeyCapsule { c1: U256::zero(), ..sealed.clone() },
+		] {
+			assert_noop!(
+				Voting::vote(Origin::signed(1), 0, capsule),
+				Error::<Test>::InvalidCapsule
+			);
+		}
+
+		assert_noop!(
+			Voting::vote(Origin::signed(6), 0, sealed.clone()),
+			Error::<Test>::InsufficientBond
+		);
+
+		assert_ok!(Voting::vote(Origin::signed(1), 0, sealed.clone()));
+		assert_eq!(
+			Voting::ballots(0, 1),
+			Some(BallotInfo { capsule: sealed, bond: 5, state: BallotState::Sealed })
+		);
+		assert_eq!(Balances::reserved_balance(1), 5);
+		System::assert_last_event(mock::Event::Voting(crate::Event::Voted(0, 1)));
+		// an account can not vote twice, even for another option.
+		assert_noop!(
+			Voting::vote(Origin::signed(1), 0, ballot(10, 2)),
+			Error::<Test>::AlreadyVoted
+		);
+
+		for who in 2..=4 {
+			assert_ok!(Voting::vote(Origin::signed(who), 0, ballot(10, 2)));
+		}
+		assert_eq!(Voting::referenda(0).map(|info| info.ballots), Some(4));
+		assert_noop!(
+			Voting::vote(Origin::signed(5), 0, ballot(10, 2)),
+			Error::<Test>::TooManyBallots
+		);
+
+		System::set_block_number(10);
+		assert_noop!(
+			Voting::vote(Origin::signed(5), 0, ballot(10, 2)),
+			Error::<Test>::VotingClosed
+		);
+	});
+}
+
+#[test]
+fn tally_counts_the_opened_ballots() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Voting::start_referendum(Origin::root(), 5, 3));
+		for (who, choice) in [(1, 2), (2, 1), (3, 2), (4, 7)] {
+			assert_ok!(Voting::vote(Origin::signed(who), 0, ballot(5, choice)));
+		}
+
+		// anyone can open the ballots once the key of the end block is stored, in several
+		// batches.
+		mine_after_end(5);
+		assert_ok!(Voting::tally(Origin::signed(9), 0, openings(vec![(1, 2), (2, 1)])));
+		assert_eq!(Voting::tally_count(0, 1), 1);
+		assert_eq!(Voting::results_of(0), None);
+		System::assert_last_event(mock::Event::Voting(crate::Event::BallotOpened(0, 2, Some(1))));
+		// the bond is released once the ballot is opened.
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Balances::reserved_balance(3), 5);
+
+		assert_ok!(Voting::tally(Origin::signed(9), 0, openings(vec![(3, 2), (4, 7)])));
+		// an option out of the range spoils the ballot.
+		assert_eq!(Voting::ballots(0, 4).map(|ballot| ballot.state), Some(BallotState::Spoiled));
+		assert_eq!(Balances::reserved_balance(4), 0);
+		System::assert_has_event(mock::Event::Voting(crate::Event::BallotOpened(0, 4, None)));
+		System::assert_last_event(mock::Event::Voting(crate::Event::ReferendumTallied(
+			0,
+			vec![1, 2, 0],
+		)));
+		assert_eq!(Voting::results_of(0), Some(vec![1, 2, 0]));
+		assert_noop!(Voting::tally(Origin::signed(9), 0, vec![]), Error::<Test>::AlreadyTallied);
+	});
+}
+
+#[test]
+fn tally_checks_the_openings() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Voting::start_referendum(Origin::root(), 5, 2));
+		assert_ok!(Voting::vote(Origin::signed(1), 0, ballot(5, 1)));
+		assert_ok!(Voting::vote(Origin::signed(2), 0, ballot(5, 2)));
+		let tally = |votes: Vec<(u64, U256)>| Voting::tally(Origin::signed(9), 0, votes);
+
+		// the key of the end block is stored by the next block.
+		System::set_block_number(6);
+		assert_noop!(tally(openings(vec![(1, 1)])), Error::<Test>::KeyNotReleased);
+
+		mine_after_end(5);
+		assert_noop!(tally(openings(vec![(1, 2)])), Error::<Test>::InvalidOpening);
+		// the openings are checked before any of them is counted.
+		assert_noop!(tally(openings(vec![(1, 1), (2, 1)])), Error::<Test>::InvalidOpening);
+		assert_noop!(tally(openings(vec![(1, 1), (1, 1)])), Error::<Test>::AlreadyOpened);
+		assert_noop!(tally(openings(vec![(3, 1)])), Error::<Test>::BallotNotFound);
+
+		assert_ok!(tally(openings(vec![(1, 1)])));
+		assert_noop!(tally(openings(vec![(1, 1)])), Error::<Test>::AlreadyOpened);
+
+		// the option decrypted with a forged key is checked with the stored key.
+		let key = released_key();
+		let x = U256::from(12345u64);
+		let forged = RawReleasedKey { h: elgamal::pow_mod(key.g, x, key.p), x, ..key };
+		let sealed = Voting::ballots(0, 2).unwrap().capsule;
+		let forged_choice = elgamal::decrypt(&forged, &sealed).unwrap();
+		assert_ne!(forged_choice, U256::from(2));
+		assert_noop!(tally(vec![(2, forged_choice)]), Error::<Test>::InvalidOpening);
+	});
+}
+
+#[test]
+fn ballots_to_another_key_are_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Voting::start_referendum(Origin::root(), 5, 2));
+		assert_ok!(Voting::start_referendum(Origin::root(), 5, 2));
+		let p = released_key().p - 2;
+		let other = RawReleasedKey { p, ..released_key() };
+		assert_ok!(Voting::vote(
+			Origin::signed(1),
+			0,
+			elgamal::encrypt(&other, 5, 1.into(), 7.into())
+		));
+		assert_ok!(Voting::vote(Origin::signed(2), 0, ballot(5, 2)));
+
+		// the ballot can never be opened, so it is rejected instead.
+		assert_noop!(
+			Voting::reject_ballots(Origin::signed(9), 0, vec![1]),
+			Error::<Test>::KeyNotReleased
+		);
+		mine_after_end(5);
+		assert_noop!(
+			Voting::tally(Origin::signed(9), 0, openings(vec![(1, 1)])),
+			Error::<Test>::KeyMismatch
+		);
+		assert_noop!(
+			Voting::reject_ballots(Origin::signed(9), 0, vec![2]),
+			Error::<Test>::CanBeOpened
+		);
+		assert_noop!(
+			Voting::reject_ballots(Origin::signed(9), 0, vec![1, 1]),
+			Error::<Test>::AlreadyOpened
+		);
+
+		assert_ok!(Voting::reject_ballots(Origin::signed(9), 0, vec![1]));
+		assert_eq!(Voting::ballots(0, 1).map(|ballot| ballot.state), Some(BallotState::Rejected));
+		System::assert_last_event(mock::Event::Voting(crate::Event::BallotRejected(0, 1, 5)));
+		// the bond of the rejected ballot is slashed.
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 95);
+		assert_eq!(Voting::results_of(0), None);
+
+		assert_ok!(Voting::tally(Origin::signed(9), 0, openings(vec![(2, 2)])));
+		System::assert_last_event(mock::Event::Voting(crate::Event::ReferendumTallied(
+			0,
+			vec![0, 1],
+		)));
+
+		// a referendum without ballots is tallied once the key is stored.
+		assert_ok!(Voting::tally(Origin::signed(9), 1, vec![]));
+		System::assert_last_event(mock::Event::Voting(crate::Event::ReferendumTallied(
+			1,
+			vec![0, 0],
+		)));
+	});
+}
+
+#[test]
+fn ballots_without_opening_are_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Voting::start_referendum(Origin::root(), 5, 2));
+		assert_ok!(Voting::vote(Origin::signed(1), 0, ballot(5, 1)));
+		assert_ok!(Voting::vote(Origin::signed(2), 0, ballot(5, 2)));
+		// a ballot to the released key with `c1 = 0` and `c2 != 0` has no opening at all.
+		Ballots::<Test>::mutate(0, 1, |ballot| {
+			if let Some(ballot) = ballot {
+				ballot.capsule.c1 = U256::zero();
+			}
+		});
+
+		mine_after_end(5);
+		assert_noop!(
+			Voting::tally(Origin::signed(9), 0, openings(vec![(1, 1)])),
+			Error::<Test>::InvalidOpening
+		);
+		assert_ok!(Voting::reject_ballots(Origin::signed(9), 0, vec![1]));
+		assert_eq!(Voting::ballots(0, 1).map(|ballot| ballot.state), Some(BallotState::Rejected));
+		assert_eq!(Balances::free_balance(1), 95);
+
+		assert_ok!(Voting::tally(Origin::signed(9), 0, openings(vec![(2, 2)])));
+		assert_eq!(Voting::results_of(0), Some(vec![0, 1]));
+	});
+}
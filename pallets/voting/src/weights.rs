@@ -0,0 +1,108 @@
+//! Weights for pallet_trex_voting
+//!
+//! Estimates from the storage accesses of each call and the ElGamal arithmetic of the openings on
+//! 48 bit keys, the benchmarks of `benchmarking.rs` are not run on the reference hardware yet.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_trex_voting.
+pub trait VotingWeight {
+    fn start_referendum() -> Weight;
+    fn vote() -> Weight;
+    fn tally(n: u32, c: u32, ) -> Weight;
+    fn reject_ballots(n: u32, ) -> Weight;
+}
+
+/// Weights for pallet_trex_voting using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> VotingWeight for SubstrateWeight<T> {
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXVoting ReferendumCount (r:1 w:1), Referenda (r:0 w:1)
+    fn start_referendum() -> Weight {
+        (25_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().writes(2 as Weight))
+    }
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXVoting Referenda (r:1 w:1), Ballots (r:1 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn vote() -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(4 as Weight))
+            .saturating_add(T::DbWeight::get().writes(3 as Weight))
+    }
+    // Storage: TREXVoting Referenda (r:1 w:1), Ballots (r:1 w:1), Tally (r:1 w:1)
+    // Storage: TREXKeys Released (r:1 w:0)
+    // Storage: System Account (r:1 w:1)
+    fn tally(n: u32, c: u32, ) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((35_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add((1_000_000 as Weight).saturating_mul(c as Weight))
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(c as Weight)))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+            .saturating_add(T::DbWeight::get().writes((3 as Weight).saturating_mul(n as Weight)))
+    }
+    // Storage: TREXVoting Referenda (r:1 w:1), Ballots (r:1 w:1)
+    // Storage: TREXKeys Released (r:1 w:0)
+    // Storage: System Account (r:1 w:1)
+    // Storage: Balances TotalIssuance (r:1 w:1)
+    fn reject_ballots(n: u32, ) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((15_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(T::DbWeight::get().reads(2 as Weight))
+            .saturating_add(T::DbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+            .saturating_add(T::DbWeight::get().writes((3 as Weight).saturating_mul(n as Weight)))
+    }
+}
+
+// For backwards compatibility and tests
+impl VotingWeight for () {
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXVoting ReferendumCount (r:1 w:1), Referenda (r:0 w:1)
+    fn start_referendum() -> Weight {
+        (25_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(2 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(2 as Weight))
+    }
+    // Storage: System Number (r:1 w:0)
+    // Storage: TREXVoting Referenda (r:1 w:1), Ballots (r:1 w:1)
+    // Storage: System Account (r:1 w:1)
+    fn vote() -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(4 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(3 as Weight))
+    }
+    // Storage: TREXVoting Referenda (r:1 w:1), Ballots (r:1 w:1), Tally (r:1 w:1)
+    // Storage: TREXKeys Released (r:1 w:0)
+    // Storage: System Account (r:1 w:1)
+    fn tally(n: u32, c: u32, ) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((35_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add((1_000_000 as Weight).saturating_mul(c as Weight))
+            .saturating_add(RocksDbWeight::get().reads(2 as Weight))
+            .saturating_add(RocksDbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(RocksDbWeight::get().reads((1 as Weight).saturating_mul(c as Weight)))
+            .saturating_add(RocksDbWeight::get().writes(1 as Weight))
+            .saturating_add(RocksDbWeight::get().writes((3 as Weight).saturating_mul(n as Weight)))
+    }
+    // Storage: TREXVoting Referenda (r:1 w:1), Ballots (r:1 w:1)
+    // Storage: TREXKeys Released (r:1 w:0)
+    // Storage: System Account (r:1 w:1)
+    // Storage: Balances TotalIssuance (r:1 w:1)
+    fn reject_ballots(n: u32, ) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((15_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(RocksDbWeight::get().reads(2 as Weight))
+            .saturating_add(RocksDbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(RocksDbWeight::get().writes(1 as Weight))
+            .saturating_add(RocksDbWeight::get().writes((3 as Weight).saturating_mul(n as Weight)))
+    }
+}
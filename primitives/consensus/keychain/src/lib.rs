@@ -0,0 +1,61 @@
+//! Primitives of the TREX keychain digest.
+//!
+//! The seal of a block is a post-runtime digest, so the runtime can not see the public key mined at
+//! the block nor the seeds of the next keychain. The miner puts them in a pre-runtime digest with
+//! `TREX_ENGINE_ID` next to the author digest, the public key as it is and the seeds as a
+//! commitment, and the import pipeline verifies the digest matches the seal.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_core::{hashing::blake2_256, H256, U256};
+use sp_runtime::{ConsensusEngineId, Digest, RuntimeDebug};
+
+/// The engine id of the TREX keychain digest.
+pub const TREX_ENGINE_ID: ConsensusEngineId = *b"trex";
+
+/// The public key mined at a block, the same as the `RawPublicKey` of `elgamal_trex`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct RawPublicKey {
+	/// Bit length of the key, which is the mining difficulty of the block.
+	pub bit_length: u32,
+	pub p: U256,
+	pub g: U256,
+	pub h: U256,
+}
+
+/// The pre-runtime digest of the keychain state of a block.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct KeychainDigest {
+	/// The public key mined at the block.
+	pub pubkey: RawPublicKey,
+	/// Blake2 hash of the SCALE encoded seeds of the keychain of the next block.
+	pub seeds_commitment: H256,
+}
+
+impl KeychainDigest {
+	/// Create the digest of a public key and the SCALE encoded seeds of the next keychain.
+	pub fn new(pubkey: RawPublicKey, encoded_seeds: &[u8]) -> Self {
+		Self { pubkey, seeds_commitment: H256(blake2_256(encoded_seeds)) }
+	}
+
+	/// Find the keychain digest in the pre-runtime digests of a block.
+	pub fn find(digest: &Digest) -> Option<Self> {
+		digest
+			.logs
+			.iter()
+			.find_map(|item| item.pre_runtime_try_to::<Self>(&TREX_ENGINE_ID))
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// API to query the keychain state of the best block.
+	pub trait KeychainApi {
+		/// The public key mined at the block.
+		fn current_pubkey() -> Option<RawPublicKey>;
+
+		/// The commitment to the seeds of the keychain of the next block.
+		fn seeds_commitment() -> Option<H256>;
+	}
+}
@@ -13,30 +13,73 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Inherents for trex
+//! Inherents for trex, which carry the private key released by the seal of the parent block.
+//! The key of a block can only be derived once the block is mined, so it is provided to the
+//! runtime by the author of the child block.
 
-use sp_inherents::{Error, InherentData, InherentIdentifier};
+#![cfg_attr(not(feature = "std"), no_std)]
 
+use codec::Encode;
+#[cfg(feature = "std")]
+use codec::Decode;
+use sp_inherents::{Error, InherentData, InherentIdentifier, IsFatalError};
+use sp_runtime::RuntimeDebug;
 use sp_std::result::Result;
+pub use trex_io::RawReleasedKey;
 
 /// The trex inherent identifier.
 pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"trexdev0";
 
-/// The type of the BABE inherent.
-pub type InherentType = String;
+/// The type of the trex inherent, the key released by the seal of the parent block.
+pub type InherentType = RawReleasedKey;
+
+/// Errors that can occur while checking the trex inherent.
+#[derive(Encode, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, thiserror::Error))]
+pub enum InherentError {
+	/// The released key of the parent block is needed by the block but it is not provided.
+	#[cfg_attr(feature = "std", error("The released key of the parent block is missing."))]
+	MissingReleasedKey,
+	/// The private key is not the discrete logarithm of its public key.
+	#[cfg_attr(feature = "std", error("The released key does not match its public key."))]
+	InvalidReleasedKey,
+}
 
-/// Provides the custom value inherent data for TREX.
-// TODO: Remove in the future. https://github.com/paritytech/substrate/issues/8029
+impl IsFatalError for InherentError {
+	fn is_fatal_error(&self) -> bool {
+		true
+	}
+}
+
+/// Auxiliary trait to extract the trex inherent data.
+pub trait TrexInherentData {
+	/// Get the released key of the parent block, if it is provided.
+	fn released_key(&self) -> Result<Option<InherentType>, Error>;
+}
+
+impl TrexInherentData for InherentData {
+	fn released_key(&self) -> Result<Option<InherentType>, Error> {
+		self.get_data(&INHERENT_IDENTIFIER)
+	}
+}
+
+/// Provides the key released by the seal of the parent block.
 #[cfg(feature = "std")]
 pub struct InherentDataProvider {
-	custom_value: InherentType,
+	released_key: Option<InherentType>,
 }
 
 #[cfg(feature = "std")]
 impl InherentDataProvider {
-	/// Create `Self` while using the default value to get the String.
-	pub fn from_default_value() -> Self {
-		Self { custom_value: "hello".to_string() }
+	/// Create `Self` with the key released by the SCALE encoded seal of the parent block, no key
+	/// is provided if the parent is not sealed, e.g. the genesis block.
+	pub fn from_seal(seal: Option<&[u8]>) -> Self {
+		Self { released_key: seal.and_then(trex_io::released_key) }
+	}
+
+	/// The released key provided by `Self`.
+	pub fn released_key(&self) -> Option<&InherentType> {
+		self.released_key.as_ref()
 	}
 }
 
@@ -44,15 +87,21 @@ impl InherentDataProvider {
 #[async_trait::async_trait]
 impl sp_inherents::InherentDataProvider for InherentDataProvider {
 	fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), Error> {
-		inherent_data.put_data(INHERENT_IDENTIFIER, &self.custom_value)
+		match self.released_key {
+			Some(ref key) => inherent_data.put_data(INHERENT_IDENTIFIER, key),
+			None => Ok(()),
+		}
 	}
 
 	async fn try_handle_error(
 		&self,
-		_: &InherentIdentifier,
-		_: &[u8],
+		identifier: &InherentIdentifier,
+		error: &[u8],
 	) -> Option<Result<(), Error>> {
-		// There is no error anymore
-		None
+		if *identifier != INHERENT_IDENTIFIER {
+			return None
+		}
+		let error = InherentError::decode(&mut &error[..]).ok()?;
+		Some(Err(Error::Application(Box::from(error))))
 	}
 }
@@ -54,6 +54,11 @@ pub const HOURS: u32 = 60;
 /// Block number of one day.
 pub const DAYS: u32 = 24 * HOURS;
 
+/// Height from which an imported block must carry a seal and the keychain digest, and its public
+/// key and seeds must be the ones committed by its parent. It is a fork height above the head of
+/// testnet2022, whose blocks were mined without the digest and are imported as before.
+pub const KEYCHAIN_DIGEST_HEIGHT: u32 = 1_500_000;
+
 pub const UPDATE_KEY_CHAIN_RANGE: u32 = 3;
 pub const MINING_WORKER_TIMEOUT: u64 = 10;
 pub const MINING_WORKER_BUILD_TIME: u64 = 10;
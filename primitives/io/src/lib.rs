@@ -0,0 +1,37 @@
+//! Released keys of TREX, which are derived natively from the PoW seals and passed to the
+//! runtime.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::U256;
+use sp_runtime::RuntimeDebug;
+
+/// A private key released by mining a block, along with its public key.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct RawReleasedKey {
+	/// Bit length of the key, which is the mining difficulty of the block.
+	pub bit_length: u32,
+	pub p: U256,
+	pub g: U256,
+	pub h: U256,
+	pub x: U256,
+}
+
+/// Derive the private key released by a SCALE encoded seal with the solutions of pollard rho
+/// method, returns `None` if the seal is invalid.
+#[cfg(feature = "std")]
+pub fn released_key(seal: &[u8]) -> Option<RawReleasedKey> {
+	use trex_pow::{utils::bigint_u256, Seal};
+
+	let seal = Seal::decode(&mut &seal[..]).ok()?;
+	let key = seal.released_key()?;
+	Some(RawReleasedKey {
+		bit_length: key.bit_length,
+		p: bigint_u256(&key.p),
+		g: bigint_u256(&key.g),
+		h: seal.pubkey.h,
+		x: bigint_u256(&key.x),
+	})
+}
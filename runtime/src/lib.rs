@@ -16,7 +16,7 @@ use sp_core::OpaqueMetadata;
 use sp_runtime::{
 	create_runtime_str, generic,
 	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, Verify},
-	transaction_validity::{TransactionSource, TransactionValidity},
+	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
 use sp_std::prelude::*;
@@ -25,7 +25,7 @@ use sp_std::prelude::*;
 use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
 // A few exports that help ease life for downstream crates.
-use trex_constants::BLOCK_TIME_SEC; //SLOT_DURATION
+use trex_constants::{deposit, BLOCK_TIME_SEC, DAYS}; //SLOT_DURATION
 pub use frame_support::{
 	construct_runtime, parameter_types,
 	traits::{KeyOwnerProofSystem, Randomness, StorageInfo, ConstU8},
@@ -97,10 +97,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -177,7 +177,25 @@ impl frame_system::Config for Runtime {
 	type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
-impl pallet_randomness_collective_flip::Config for Runtime {}
+parameter_types! {
+	/// The released keys outlive the ciphertexts of `TREXRetentionPeriod`, the reveal period of the
+	/// auctions and leave four weeks to tally a referendum.
+	pub const KeyRetention: BlockNumber = 28 * DAYS;
+}
+
+impl pallet_trex_keys::Config for Runtime {
+	type KeyRetention = KeyRetention;
+	type KeysWeight = pallet_trex_keys::weights::SubstrateWeight<Runtime>;
+}
+
+impl pallet_trex_keychain::Config for Runtime {
+	type KeychainWeight = pallet_trex_keychain::weights::SubstrateWeight<Runtime>;
+}
+
+impl pallet_trex_randomness::Config for Runtime {
+	type ReleasedKeys = TREXKeys;
+	type RandomnessWeight = pallet_trex_randomness::weights::SubstrateWeight<Runtime>;
+}
 
 parameter_types! {
 	pub const MaxAuthorities: u32 = 32;
@@ -246,9 +264,104 @@ impl pallet_storage::Config for Runtime {
 	type Event = Event;
 }
 
+parameter_types! {
+	pub const TREXMaxCipherLen: u32 = 64 * 1024;
+	pub const TREXDepositPerItem: Balance = deposit(1, 0);
+	pub const TREXDepositPerByte: Balance = deposit(0, 1);
+	pub const TREXRetentionPeriod: BlockNumber = 7 * DAYS;
+	pub const TREXMaxExpiredPerBlock: u32 = 50;
+	pub const TREXUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+}
+
 impl pallet_trex::Config for Runtime {
 	type Event = Event;
+	type MaxCipherLen = TREXMaxCipherLen;
 	type TREXWeight = pallet_trex::weights::SubstrateWeight<Runtime>;
+	type Currency = Balances;
+	type DepositPerItem = TREXDepositPerItem;
+	type DepositPerByte = TREXDepositPerByte;
+	type RetentionPeriod = TREXRetentionPeriod;
+	type MaxExpiredPerBlock = TREXMaxExpiredPerBlock;
+	type UnsignedPriority = TREXUnsignedPriority;
+	type ReleasedKeys = TREXKeys;
+}
+
+parameter_types! {
+	pub const AuctionDeposit: Balance = deposit(1, 0);
+	pub const AuctionMinBidBond: Balance = deposit(1, 0);
+	pub const AuctionRevealPeriod: BlockNumber = DAYS;
+	pub const AuctionMaxBids: u32 = 100;
+}
+
+impl pallet_trex_auction::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type AuctionDeposit = AuctionDeposit;
+	type MinBidBond = AuctionMinBidBond;
+	type RevealPeriod = AuctionRevealPeriod;
+	type MaxBids = AuctionMaxBids;
+	type ReleasedKeys = TREXKeys;
+	type AuctionWeight = pallet_trex_auction::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const VotingMaxChoices: u32 = 16;
+	pub const VotingMaxBallots: u32 = 1_000;
+	pub const VotingBond: Balance = deposit(1, 0);
+}
+
+impl pallet_trex_voting::Config for Runtime {
+	type Event = Event;
+	type ReferendumOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxChoices = VotingMaxChoices;
+	type MaxBallots = VotingMaxBallots;
+	type Currency = Balances;
+	type VotingBond = VotingBond;
+	type ReleasedKeys = TREXKeys;
+	type VotingWeight = pallet_trex_voting::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const TimelockDepositPerItem: Balance = deposit(1, 0);
+	pub const TimelockDepositPerByte: Balance = deposit(0, 1);
+	pub const TimelockMaxCallLen: u32 = 16 * 1024;
+	pub TimelockMaxCallWeight: Weight = NORMAL_DISPATCH_RATIO * BlockWeights::get().max_block / 2;
+}
+
+impl pallet_trex_timelock::Config for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type DepositPerItem = TimelockDepositPerItem;
+	type DepositPerByte = TimelockDepositPerByte;
+	type MaxCallLen = TimelockMaxCallLen;
+	type MaxCallWeight = TimelockMaxCallWeight;
+	type ReleasedKeys = TREXKeys;
+	type TimelockWeight = pallet_trex_timelock::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const MempoolMaxCallLen: u32 = 16 * 1024;
+	pub const MempoolMaxPending: u32 = 256;
+	pub MempoolMaxPendingWeight: Weight = NORMAL_DISPATCH_RATIO * BlockWeights::get().max_block / 4;
+}
+
+impl pallet_trex_mempool::Config for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type MaxCallLen = MempoolMaxCallLen;
+	type MaxPending = MempoolMaxPending;
+	type MaxPendingWeight = MempoolMaxPendingWeight;
+	type ReleasedKeys = TREXKeys;
+	type MempoolWeight = pallet_trex_mempool::weights::SubstrateWeight<Runtime>;
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
+where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = UncheckedExtrinsic;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -258,17 +371,26 @@ construct_runtime!(
 		NodeBlock = opaque::Block,
 		UncheckedExtrinsic = UncheckedExtrinsic
 	{
-		System: frame_system,
-		RandomnessCollectiveFlip: pallet_randomness_collective_flip,
-		Timestamp: pallet_timestamp,
-		Balances: pallet_balances,
-		TransactionPayment: pallet_transaction_payment,
-		Sudo: pallet_sudo,
+		// The indices of the pallets of the first runtime are kept, the index 1 of the removed
+		// `RandomnessCollectiveFlip` is not reused.
+		System: frame_system = 0,
+		// The released keys are stored by the first inherent, before the other pallets read them.
+		TREXKeys: pallet_trex_keys = 10,
+		TREXKeychain: pallet_trex_keychain = 11,
+		TREXRandomness: pallet_trex_randomness = 12,
+		Timestamp: pallet_timestamp = 2,
+		Balances: pallet_balances = 3,
+		TransactionPayment: pallet_transaction_payment = 4,
+		Sudo: pallet_sudo = 5,
 		// Include the custom logic from the pallet-difficulty in the runtime.
-		DifficultyModule: pallet_difficulty,
-		StorageModule: pallet_storage,
-		TREXModule: pallet_trex,
-		Rewards: pallet_rewards,
+		DifficultyModule: pallet_difficulty = 6,
+		StorageModule: pallet_storage = 7,
+		TREXModule: pallet_trex = 8,
+		TREXAuction: pallet_trex_auction = 13,
+		TREXVoting: pallet_trex_voting = 14,
+		TREXTimelock: pallet_trex_timelock = 15,
+		TREXMempool: pallet_trex_mempool = 16,
+		Rewards: pallet_rewards = 9,
 	}
 );
 
@@ -288,6 +410,7 @@ pub type SignedExtra = (
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_trex_mempool::CheckEncryptedCall<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -300,8 +423,15 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
+/// Storage migrations run on runtime upgrade, before the hooks of the pallets.
+pub type Migrations = (
+	pallet_trex::migrations::v1::MigrateToV1<Runtime>,
+	pallet_trex_randomness::migrations::v1::RemoveCollectiveFlip<Runtime>,
+);
+
 impl_runtime_apis! {
 	impl pallet_storage_runtime_api::SumStorageApi<Block> for Runtime{
 		fn get_sum() -> u32{
@@ -309,10 +439,43 @@ impl_runtime_apis! {
 		}
 	}
 
-	impl pallet_difficulty_runtime_api::DiffAdjustmentApi<Block> for Runtime{
+	impl pallet_difficulty_runtime_api::DiffAdjustmentApi<Block, BlockNumber> for Runtime{
 		fn get_avg_blocktime() -> u32{
 			DifficultyModule::get_avg_blocktime()
 		}
+
+		fn estimate_release_block(
+			timestamp: u64,
+		) -> Option<pallet_difficulty_runtime_api::ReleaseEstimate<BlockNumber>> {
+			DifficultyModule::estimate_release_block(timestamp)
+		}
+	}
+
+	impl pallet_trex_runtime_api::CiphertextApi<Block, AccountId, Balance, BlockNumber> for Runtime {
+		fn ciphertexts_by_release(
+			release_height: BlockNumber,
+			offset: u32,
+			limit: u32,
+		) -> Vec<pallet_trex_runtime_api::CiphertextRecord<AccountId, Balance, BlockNumber>> {
+			TREXModule::ciphertexts_by_release(release_height, offset, limit)
+		}
+
+		fn ciphertexts_by_sender(
+			who: AccountId,
+			offset: u32,
+			limit: u32,
+		) -> Vec<pallet_trex_runtime_api::CiphertextRecord<AccountId, Balance, BlockNumber>> {
+			TREXModule::ciphertexts_by_sender(who, offset, limit)
+		}
+
+		fn pending_releases(
+			from: BlockNumber,
+			to: BlockNumber,
+			offset: u32,
+			limit: u32,
+		) -> Vec<pallet_trex_runtime_api::CiphertextRecord<AccountId, Balance, BlockNumber>> {
+			TREXModule::pending_releases(from, to, offset, limit)
+		}
 	}
 
 	impl sp_api::Core<Block> for Runtime {
@@ -416,6 +579,16 @@ impl_runtime_apis! {
 			pallet_difficulty::Pallet::<Runtime>::difficulty().unwrap()
 		}
 	}
+
+	impl trex_keychain::KeychainApi<Block> for Runtime {
+		fn current_pubkey() -> Option<trex_keychain::RawPublicKey> {
+			TREXKeychain::current_pubkey()
+		}
+
+		fn seeds_commitment() -> Option<sp_core::H256> {
+			TREXKeychain::seeds_commitment()
+		}
+	}
 	//
 	// impl kulupu_primitives::AlgorithmApi<Block> for Runtime {
 	// 	fn identifier() -> [u8; 8] {
@@ -442,6 +615,13 @@ impl_runtime_apis! {
 			list_benchmark!(list, extra, pallet_timestamp, Timestamp);
 			list_benchmark!(list, extra, pallet_difficulty, DifficultyModule);
 			list_benchmark!(list, extra, pallet_trex, TREXModule);
+			list_benchmark!(list, extra, pallet_trex_auction, TREXAuction);
+			list_benchmark!(list, extra, pallet_trex_voting, TREXVoting);
+			list_benchmark!(list, extra, pallet_trex_timelock, TREXTimelock);
+			list_benchmark!(list, extra, pallet_trex_mempool, TREXMempool);
+			list_benchmark!(list, extra, pallet_trex_randomness, TREXRandomness);
+			list_benchmark!(list, extra, pallet_trex_keys, TREXKeys);
+			list_benchmark!(list, extra, pallet_trex_keychain, TREXKeychain);
 
 			let storage_info = AllPalletsWithSystem::storage_info();
 
@@ -481,6 +661,13 @@ impl_runtime_apis! {
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
 			add_benchmark!(params, batches, pallet_difficulty, DifficultyModule);
 			add_benchmark!(params, batches, pallet_trex, TREXModule);
+			add_benchmark!(params, batches, pallet_trex_auction, TREXAuction);
+			add_benchmark!(params, batches, pallet_trex_voting, TREXVoting);
+			add_benchmark!(params, batches, pallet_trex_timelock, TREXTimelock);
+			add_benchmark!(params, batches, pallet_trex_mempool, TREXMempool);
+			add_benchmark!(params, batches, pallet_trex_randomness, TREXRandomness);
+			add_benchmark!(params, batches, pallet_trex_keys, TREXKeys);
+			add_benchmark!(params, batches, pallet_trex_keychain, TREXKeychain);
 
 			Ok(batches)
 		}